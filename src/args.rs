@@ -0,0 +1,216 @@
+use cfg_if::cfg_if;
+use log::warn;
+
+use crate::camera::{KeyBindings, ZoomMode};
+
+/// Scene settings that can be given on the command line (native) or as URL query parameters
+/// (wasm), e.g. `--clear 0.1,0.1,0.1 --fov 45 --image path.png --eye -6,6,6 --damping 0.8
+/// --aspect 1.7778 --letterbox 0,0,0 --idle-timeout 300 --fps-cap 60 --flipbook 2.5
+/// --premultiplied true --step-angle 5 --conservative true --fragment-shader path.wgsl
+/// --downscale-oversized true --image-sequence frames/ --base-color 0.5,0.5,0.5
+/// --projector 6,6,6 --projector -6,6,-6 --model scan.obj --grid 3 --msaa 4 --keys wasd
+/// --zoom fov --orbit true` or
+/// `?clear=0.1,0.1,0.1&fov=45`.
+#[derive(Default)]
+pub struct SceneOptions {
+    /// Colors are sRGB encoded, as shown by a color picker
+    pub clear_color: Option<wgpu::Color>,
+    /// Vertical field of view in degrees
+    pub fov: Option<f32>,
+    pub image: Option<String>,
+    /// OBJ file in `res` to project onto instead of the test plane
+    pub model: Option<String>,
+    /// Repeat the meshes on an n × n grid
+    pub grid: Option<u32>,
+    pub eye: Option<glam::Vec3>,
+    /// Camera motion damping, see `CameraController::with_damping`
+    pub damping: Option<f32>,
+    /// Fixed width / height ratio the scene is letterboxed to
+    pub aspect: Option<f32>,
+    pub letterbox_color: Option<wgpu::Color>,
+    /// Color of surfaces outside the projected image
+    pub base_color: Option<wgpu::Color>,
+    /// Eyes of projectors blended in place of the main one, each projecting the image.  Can be
+    /// given more than once.
+    pub projectors: Vec<glam::Vec3>,
+    /// Exit after this long without input
+    pub idle_timeout: Option<std::time::Duration>,
+    /// Maximum frames per second
+    pub fps_cap: Option<u32>,
+    /// Seconds to loop through the loaded images, or the image sequence, in
+    pub flipbook: Option<f32>,
+    /// Directory of equally sized frames to project instead of the image (native only)
+    pub image_sequence: Option<String>,
+    /// Blend the image with premultiplied alpha
+    pub premultiplied: bool,
+    /// Move the camera this many degrees per key press, see `CameraController::step_mode`
+    pub step_angle: Option<f32>,
+    /// Camera movement keys, `esdf` (the default) or `wasd`
+    pub key_bindings: Option<KeyBindings>,
+    /// What scrolling does, `dolly` (the default) or `fov`
+    pub zoom_mode: Option<ZoomMode>,
+    /// Drag and scroll with an `OrbitController`, whose pitch can't flip over the poles
+    pub orbit: bool,
+    /// Use conservative rasterization where supported, so thin geometry isn't missed
+    pub conservative: bool,
+    /// Samples per pixel for antialiasing, see `State::set_sample_count`
+    pub msaa: Option<u32>,
+    /// WGSL source replacing the built-in `fs_main`; see `create_custom_fragment_shader` for the
+    /// interface it has to provide.  Given as a file path on the command line.
+    pub fragment_shader: Option<String>,
+    /// Shrink images larger than the device's texture size limit instead of failing to load them
+    pub downscale_oversized: bool,
+}
+
+impl SceneOptions {
+    pub fn from_env() -> Self {
+        let mut options = Self::default();
+        for (key, value) in pairs() {
+            options.set(&key, &value);
+        }
+        options
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "clear" => match parse_color(value) {
+                Some(color) => self.clear_color = Some(color),
+                None => warn!("Ignoring invalid clear color: {value}"),
+            },
+            "fov" => match value.parse() {
+                Ok(fov) => self.fov = Some(fov),
+                Err(_) => warn!("Ignoring invalid fov: {value}"),
+            },
+            "image" => self.image = Some(value.to_string()),
+            "model" => self.model = Some(value.to_string()),
+            "grid" => match value.parse() {
+                Ok(n) => self.grid = Some(n),
+                Err(_) => warn!("Ignoring invalid grid size: {value}"),
+            },
+            "eye" => match parse_floats::<3>(value) {
+                Some(eye) => self.eye = Some(eye.into()),
+                None => warn!("Ignoring invalid eye position: {value}"),
+            },
+            "damping" => match value.parse() {
+                Ok(damping) => self.damping = Some(damping),
+                Err(_) => warn!("Ignoring invalid damping: {value}"),
+            },
+            "aspect" => match value.parse() {
+                Ok(aspect) => self.aspect = Some(aspect),
+                Err(_) => warn!("Ignoring invalid aspect: {value}"),
+            },
+            "letterbox" => match parse_color(value) {
+                Some(color) => self.letterbox_color = Some(color),
+                None => warn!("Ignoring invalid letterbox color: {value}"),
+            },
+            "base-color" => match parse_color(value) {
+                Some(color) => self.base_color = Some(color),
+                None => warn!("Ignoring invalid base color: {value}"),
+            },
+            "projector" => match parse_floats::<3>(value) {
+                Some(eye) => self.projectors.push(eye.into()),
+                None => warn!("Ignoring invalid projector position: {value}"),
+            },
+            "idle-timeout" => match value.parse().map(std::time::Duration::try_from_secs_f32) {
+                Ok(Ok(timeout)) => self.idle_timeout = Some(timeout),
+                _ => warn!("Ignoring invalid idle timeout: {value}"),
+            },
+            "fps-cap" => match value.parse() {
+                Ok(fps) => self.fps_cap = Some(fps),
+                Err(_) => warn!("Ignoring invalid fps cap: {value}"),
+            },
+            "image-sequence" => self.image_sequence = Some(value.to_string()),
+            "flipbook" => match value.parse() {
+                Ok(duration) if duration > 0.0 => self.flipbook = Some(duration),
+                _ => warn!("Ignoring invalid flipbook duration: {value}"),
+            },
+            "premultiplied" => match value.parse() {
+                Ok(premultiplied) => self.premultiplied = premultiplied,
+                Err(_) => warn!("Ignoring invalid premultiplied flag: {value}"),
+            },
+            "step-angle" => match value.parse() {
+                Ok(angle) => self.step_angle = Some(angle),
+                Err(_) => warn!("Ignoring invalid step angle: {value}"),
+            },
+            "keys" => match value {
+                "esdf" => self.key_bindings = Some(KeyBindings::default()),
+                "wasd" => self.key_bindings = Some(KeyBindings::wasd()),
+                _ => warn!("Ignoring unknown key layout: {value}"),
+            },
+            "zoom" => match value {
+                "dolly" => self.zoom_mode = Some(ZoomMode::Dolly),
+                "fov" => self.zoom_mode = Some(ZoomMode::Fov),
+                _ => warn!("Ignoring unknown zoom mode: {value}"),
+            },
+            "orbit" => match value.parse() {
+                Ok(orbit) => self.orbit = orbit,
+                Err(_) => warn!("Ignoring invalid orbit flag: {value}"),
+            },
+            "conservative" => match value.parse() {
+                Ok(conservative) => self.conservative = conservative,
+                Err(_) => warn!("Ignoring invalid conservative flag: {value}"),
+            },
+            "msaa" => match value.parse() {
+                Ok(count) => self.msaa = Some(count),
+                Err(_) => warn!("Ignoring invalid sample count: {value}"),
+            },
+            "fragment-shader" => match std::fs::read_to_string(value) {
+                Ok(source) => self.fragment_shader = Some(source),
+                Err(e) => warn!("Ignoring fragment shader {value}: {e}"),
+            },
+            "downscale-oversized" => match value.parse() {
+                Ok(downscale) => self.downscale_oversized = downscale,
+                Err(_) => warn!("Ignoring invalid downscale flag: {value}"),
+            },
+            _ => warn!("Ignoring unknown option: {key}"),
+        }
+    }
+}
+
+fn parse_floats<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let mut out = [0.0; N];
+    let mut parts = value.split(',');
+    for v in out.iter_mut() {
+        *v = parts.next()?.trim().parse().ok()?;
+    }
+    parts.next().is_none().then_some(out)
+}
+
+/// `r,g,b` or `r,g,b,a`
+fn parse_color(value: &str) -> Option<wgpu::Color> {
+    let [r, g, b, a] = parse_floats::<4>(value)
+        .or_else(|| parse_floats::<3>(value).map(|[r, g, b]| [r, g, b, 1.0]))?;
+    Some(wgpu::Color {
+        r: r as f64,
+        g: g as f64,
+        b: b as f64,
+        a: a as f64,
+    })
+}
+
+/// Raw key/value pairs from the command line or the page URL
+fn pairs() -> Vec<(String, String)> {
+    cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            let search = web_sys::window()
+                .and_then(|win| win.location().search().ok())
+                .unwrap_or_default();
+            search
+                .trim_start_matches('?')
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        } else {
+            let mut pairs = Vec::new();
+            let mut args = std::env::args().skip(1);
+            while let Some(arg) = args.next() {
+                match (arg.strip_prefix("--"), args.next()) {
+                    (Some(key), Some(value)) => pairs.push((key.to_string(), value)),
+                    _ => warn!("Ignoring argument: {arg}"),
+                }
+            }
+            pairs
+        }
+    }
+}
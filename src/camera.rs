@@ -1,12 +1,150 @@
-use crate::{PhysicalKey, WindowEvent, KeyCode};
+use crate::{PhysicalKey, WindowEvent, KeyCode, MouseScrollDelta};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Movement {
     Forward,
     Left,
     Backward,
     Right,
+    Up,
+    Down,
 }
 
+/// Keys moving the camera, see [`CameraController::with_bindings`].  The default is the ESDF
+/// layout, with R and W raising and lowering the camera.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+}
+
+impl KeyBindings {
+    pub fn wasd() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            backward: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            up: KeyCode::KeyE,
+            down: KeyCode::KeyQ,
+        }
+    }
+
+    /// The movement `key` is bound to, if any
+    pub fn movement(&self, key: KeyCode) -> Option<Movement> {
+        [
+            (self.forward, Movement::Forward),
+            (self.backward, Movement::Backward),
+            (self.left, Movement::Left),
+            (self.right, Movement::Right),
+            (self.up, Movement::Up),
+            (self.down, Movement::Down),
+        ]
+        .into_iter()
+        .find_map(|(bound, movement)| (bound == key).then_some(movement))
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyE,
+            backward: KeyCode::KeyD,
+            left: KeyCode::KeyS,
+            right: KeyCode::KeyF,
+            up: KeyCode::KeyR,
+            down: KeyCode::KeyW,
+        }
+    }
+}
+
+/// How the camera maps view space onto the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ProjectionKind {
+    Perspective,
+    /// Parallel projection sized to match the perspective framing at the target distance
+    Orthographic,
+}
+
+/// Fisheye lens of a [`Projector`], how far from the image center a direction lands for its
+/// angle `θ` off the axis.  Unlike a perspective projection, these reach 180° and past, e.g. for
+/// a dome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Fisheye {
+    /// Distance proportional to `θ`, like most dome projection lenses
+    Equidistant,
+    /// Distance proportional to `sin(θ / 2)`, keeping areas in proportion
+    Equisolid,
+}
+
+impl Fisheye {
+    /// Distance from the image center of a direction `theta` off the axis, 1 at `half_fov`
+    fn radius(self, theta: f32, half_fov: f32) -> f32 {
+        match self {
+            Self::Equidistant => theta / half_fov,
+            Self::Equisolid => (theta * 0.5).sin() / (half_fov * 0.5).sin(),
+        }
+    }
+}
+
+/// Polygon in projector texture coordinates outside which a [`Projector`] leaves surfaces at
+/// its base color, e.g. to map onto a single facade.  Holds up to [`Self::MAX_VERTICES`]
+/// vertices, the size of its array in the shader's `ProjectorUniform`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MaskPolygon {
+    vertices: [glam::Vec2; Self::MAX_VERTICES],
+    len: usize,
+}
+
+impl MaskPolygon {
+    pub const MAX_VERTICES: usize = 16;
+
+    /// Polygon through `vertices` in order, with `u` pointing right and `v` down.  Vertices
+    /// past [`Self::MAX_VERTICES`] are dropped with a warning; fewer than 3 enclose nothing.
+    pub fn new(vertices: &[glam::Vec2]) -> Self {
+        if vertices.len() > Self::MAX_VERTICES {
+            log::warn!(
+                "Mask polygon has {} vertices, keeping the first {}",
+                vertices.len(),
+                Self::MAX_VERTICES
+            );
+        }
+        let len = vertices.len().min(Self::MAX_VERTICES);
+        let mut polygon = Self {
+            vertices: [glam::Vec2::ZERO; Self::MAX_VERTICES],
+            len,
+        };
+        polygon.vertices[..len].copy_from_slice(&vertices[..len]);
+        polygon
+    }
+
+    pub fn vertices(&self) -> &[glam::Vec2] {
+        &self.vertices[..self.len]
+    }
+
+    /// Whether `uv` is inside by the even-odd rule, so self-intersecting polygons work too.  The
+    /// CPU reference for `in_mask` in the shader.
+    pub fn contains(&self, uv: glam::Vec2) -> bool {
+        let vertices = self.vertices();
+        let mut inside = false;
+        let mut j = vertices.len().wrapping_sub(1);
+        for (i, &a) in vertices.iter().enumerate() {
+            let b = vertices[j];
+            if (a.y > uv.y) != (b.y > uv.y) && uv.x < (b.x - a.x) * (uv.y - a.y) / (b.y - a.y) + a.x
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+}
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Camera {
     pub eye: glam::Vec3,
     pub target: glam::Vec3,
@@ -15,21 +153,146 @@ pub struct Camera {
     pub fovy: f32,
     pub znear: f32,
     pub zfar: f32,
+    pub kind: ProjectionKind,
 }
 
 impl Camera {
     const SPEED: f32 = 0.2;
+
+    /// Perspective camera looking from `eye` at `target`.  Positions can be anything convertible
+    /// to `glam::Vec3`, e.g. `[f32; 3]`, or `mint::Vector3` with the `mint` feature.
+    pub fn new(
+        eye: impl Into<glam::Vec3>,
+        target: impl Into<glam::Vec3>,
+        up: impl Into<glam::Vec3>,
+        aspect: f32,
+        fovy: f32,
+    ) -> Self {
+        Self {
+            eye: eye.into(),
+            target: target.into(),
+            up: up.into(),
+            aspect,
+            fovy,
+            znear: 0.1,
+            zfar: 100.0,
+            kind: ProjectionKind::Perspective,
+        }
+    }
+
+    /// Write the pose and lens to `path` as RON, so [`Camera::load`] can restore the exact view
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Camera written by [`Camera::save`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
     pub fn build_view_projection_matrix(&self) -> glam::Mat4 {
         let view = glam::Mat4::look_at_rh(self.eye, self.target, self.up);
-        let proj = glam::Mat4::perspective_rh(
-            self.fovy,
-            self.aspect,
-            self.znear,
-            self.zfar,
-        );
+        let proj = match self.kind {
+            ProjectionKind::Perspective => {
+                glam::Mat4::perspective_rh(self.fovy, self.aspect, self.znear, self.zfar)
+            }
+            ProjectionKind::Orthographic => {
+                let half_height = self.ortho_half_height();
+                let half_width = half_height * self.aspect;
+                glam::Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.znear,
+                    self.zfar,
+                )
+            }
+        };
         proj * view
     }
 
+    /// Half the height of the orthographic view, matching the perspective one at the target
+    fn ortho_half_height(&self) -> f32 {
+        self.eye.distance(self.target) * (self.fovy * 0.5).tan()
+    }
+
+    /// Switch between perspective and orthographic projection
+    pub fn toggle_projection(&mut self) {
+        self.kind = match self.kind {
+            ProjectionKind::Perspective => ProjectionKind::Orthographic,
+            ProjectionKind::Orthographic => ProjectionKind::Perspective,
+        };
+    }
+
+    /// Minimal glTF 2.0 document holding this camera as its only node, for use in other tools.
+    /// The crate is Y-up like glTF and both cameras look down their local -Z, so the node
+    /// transform is just the inverse of the view matrix.
+    pub fn gltf_json(&self) -> String {
+        let view = glam::Mat4::look_at_rh(self.eye, self.target, self.up);
+        let (_, rotation, translation) = view.inverse().to_scale_rotation_translation();
+        let camera = match self.kind {
+            ProjectionKind::Perspective => format!(
+                r#""type": "perspective", "perspective": {{ "aspectRatio": {}, "yfov": {}, "znear": {}, "zfar": {} }}"#,
+                self.aspect, self.fovy, self.znear, self.zfar
+            ),
+            ProjectionKind::Orthographic => {
+                let ymag = self.ortho_half_height();
+                format!(
+                    r#""type": "orthographic", "orthographic": {{ "xmag": {}, "ymag": {}, "znear": {}, "zfar": {} }}"#,
+                    ymag * self.aspect,
+                    ymag,
+                    self.znear,
+                    self.zfar
+                )
+            }
+        };
+        format!(
+            r#"{{
+  "asset": {{ "version": "2.0", "generator": "image-projection" }},
+  "scene": 0,
+  "scenes": [{{ "nodes": [0] }}],
+  "nodes": [{{
+    "name": "Camera",
+    "camera": 0,
+    "rotation": [{}, {}, {}, {}],
+    "translation": [{}, {}, {}]
+  }}],
+  "cameras": [{{ {camera} }}]
+}}
+"#,
+            rotation.x,
+            rotation.y,
+            rotation.z,
+            rotation.w,
+            translation.x,
+            translation.y,
+            translation.z
+        )
+    }
+
+    /// Where `world` lands in this camera's image, as texture coordinates; see
+    /// [`Projector::project_point`], which does the same for the projected image.
+    pub fn project_point(&self, world: glam::Vec3) -> Option<glam::Vec2> {
+        let clip = self.build_view_projection_matrix() * world.extend(1.0);
+        clip_to_tex_coords(clip, true).filter(is_in_image)
+    }
+
+    /// Ray `(origin, direction)` through `pixel` of a `viewport` pixels large image, with pixels
+    /// counted from the top-left corner like cursor positions.  The origin is on the near plane
+    /// and the direction is normalized.
+    pub fn screen_ray(&self, pixel: glam::Vec2, viewport: glam::Vec2) -> (glam::Vec3, glam::Vec3) {
+        let ndc = glam::vec2(pixel.x / viewport.x, 1.0 - pixel.y / viewport.y) * 2.0 - 1.0;
+        let clip_to_world = self.build_view_projection_matrix().inverse();
+        let near = clip_to_world.project_point3(ndc.extend(0.0));
+        let far = clip_to_world.project_point3(ndc.extend(1.0));
+        (near, (far - near).normalize())
+    }
+
     pub fn update(&mut self, direction: Movement) {
         let fwd = self.target - self.eye;
         let fwd_norm = fwd.normalize();
@@ -57,69 +320,834 @@ impl Camera {
                 let right = fwd_norm.cross(self.up);
                 self.eye = self.target - (fwd - right * Self::SPEED).normalize() * fwd_mag;
             }
+            Movement::Up => {
+                self.eye += self.up * Self::SPEED;
+                self.target += self.up * Self::SPEED;
+            }
+            Movement::Down => {
+                self.eye -= self.up * Self::SPEED;
+                self.target -= self.up * Self::SPEED;
+            }
         }
     }
 }
 
+/// Where the image is projected from.  Unlike the [`Camera`] it doesn't follow the viewer, so
+/// the image stays put on the surfaces it lands on, like a slide projector in a room.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Projector {
+    pub eye: glam::Vec3,
+    pub target: glam::Vec3,
+    pub up: glam::Vec3,
+    pub aspect: f32,
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+    /// Linear color of surfaces outside the projected image
+    #[serde(with = "serde_color")]
+    pub base_color: wgpu::Color,
+    /// Leave geometry behind the projector at the base color.  Otherwise the perspective
+    /// divide projects a mirrored copy of the image onto it.
+    pub reject_behind: bool,
+    /// Share of this projector's image where it overlaps others, see `State::add_projector`
+    pub weight: f32,
+    /// Blend the image over `base_color` by its alpha, like a stencil, e.g. to project a logo
+    /// onto an object.  Otherwise alpha is the image's transparency.
+    pub alpha_as_mask: bool,
+    /// Height of the image in world units for a parallel projection, e.g. a laser or line
+    /// projector, which ignores `fovy`.  `None` projects in perspective.  Parallel rays have
+    /// no behind, so `reject_behind` doesn't apply.
+    pub ortho_height: Option<f32>,
+    /// Projection matrix used as is in place of `fovy`, `aspect` and `ortho_height`, e.g. an
+    /// off-center frustum from [`Self::from_intrinsics`]
+    pub projection: Option<glam::Mat4>,
+    /// Map directions to the image through a fisheye lens instead of a perspective projection.
+    /// `fovy` is then the angle across the circle filling the image height, which may pass
+    /// 180°; the frustum and previews through the projector are limited to a perspective
+    /// [`Self::MAX_PERSPECTIVE_FOV`] wide.  Distortion doesn't apply.
+    pub fisheye: Option<Fisheye>,
+    /// Lens shift: offset of the principal point `cx`, `cy` as a fraction of the image, `x`
+    /// pointing right and `y` down as in OpenCV.  Slides the image over the surfaces without
+    /// turning the projector.
+    pub lens_shift: glam::Vec2,
+    /// Brown-Conrady lens distortion `[k1, k2, p1, p2]` as calibrated by OpenCV, see
+    /// [`Self::distort_point`].  All zeros is an ideal lens.
+    pub distortion: [f32; 4],
+    /// Only project inside this polygon of the image, see [`Self::with_mask_polygon`]
+    pub mask_polygon: Option<MaskPolygon>,
+    /// Mirror the image left to right, e.g. for rear projection
+    pub flip_horizontal: bool,
+    /// Mirror the image top to bottom, e.g. for a projector hung upside down
+    pub flip_vertical: bool,
+    /// Hold the projector where it is while it's being animated, e.g. to look around a
+    /// projected frame with the camera
+    pub paused: bool,
+}
+
+impl Projector {
+    /// Widest perspective stand-in for a fisheye projector, see `fisheye`
+    pub const MAX_PERSPECTIVE_FOV: f32 = 170.0 * std::f32::consts::PI / 180.0;
+
+    /// Projector at the pose and projection of `camera`, so the image fills its view
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            eye: camera.eye,
+            target: camera.target,
+            up: camera.up,
+            aspect: camera.aspect,
+            fovy: camera.fovy,
+            znear: camera.znear,
+            zfar: camera.zfar,
+            base_color: wgpu::Color {
+                r: 0.2,
+                g: 0.2,
+                b: 0.2,
+                a: 1.0,
+            },
+            reject_behind: true,
+            weight: 1.0,
+            alpha_as_mask: false,
+            ortho_height: match camera.kind {
+                ProjectionKind::Perspective => None,
+                ProjectionKind::Orthographic => Some(2.0 * camera.ortho_half_height()),
+            },
+            projection: None,
+            fisheye: None,
+            lens_shift: glam::Vec2::ZERO,
+            distortion: [0.0; 4],
+            mask_polygon: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            paused: false,
+        }
+    }
+
+    /// Projector at `eye` aiming at `target`, with a square image 45° high.  The pose is kept
+    /// as given, so [`Self::position`] and [`Self::forward`] report it exactly.
+    pub fn from_look_at(
+        eye: impl Into<glam::Vec3>,
+        target: impl Into<glam::Vec3>,
+        up: impl Into<glam::Vec3>,
+    ) -> Self {
+        let camera = Camera::new(eye, target, up, 1.0, std::f32::consts::FRAC_PI_4);
+        Self::from_camera(&camera)
+    }
+
+    /// Where the image is projected from
+    pub fn position(&self) -> glam::Vec3 {
+        self.eye
+    }
+
+    /// Unit direction the image is projected in, zero if the eye is on the target
+    pub fn forward(&self) -> glam::Vec3 {
+        (self.target - self.eye).normalize_or_zero()
+    }
+
+    /// Orientation taking the projector's own axes, looking down -Z with +Y up, to the world
+    pub fn rotation(&self) -> glam::Quat {
+        let view = glam::Mat4::look_at_rh(self.eye, self.target, self.up);
+        glam::Quat::from_mat4(&view.inverse()).normalize()
+    }
+
+    /// Turn the projector to `rotation` in place, keeping its distance to the target
+    pub fn with_rotation(self, rotation: glam::Quat) -> Self {
+        self.with_rotation_translation(rotation, self.eye)
+    }
+
+    /// Move the projector to `translation` and turn it to `rotation`, keeping its distance to
+    /// the target
+    pub fn with_rotation_translation(
+        mut self,
+        rotation: glam::Quat,
+        translation: glam::Vec3,
+    ) -> Self {
+        let distance = self.eye.distance(self.target);
+        self.eye = translation;
+        self.target = translation + rotation * glam::Vec3::NEG_Z * distance;
+        self.up = rotation * glam::Vec3::Y;
+        self
+    }
+
+    /// Turn a fraction `t` of the way to `rotation` along the shortest arc, at an even angular
+    /// speed unlike interpolating angles.  `t` 0 leaves the projector as it is.
+    pub fn slerp_to(self, rotation: glam::Quat, t: f32) -> Self {
+        if t <= 0.0 {
+            return self;
+        }
+        self.with_rotation(self.rotation().slerp(rotation, t.min(1.0)))
+    }
+
+    /// Projector matching a calibrated pinhole model: focal lengths `fx`, `fy` and principal
+    /// point `cx`, `cy` in pixels of a `width` x `height` image, with `y` pointing down as in
+    /// OpenCV.  The frustum is off-center wherever the principal point is.  It sits at the
+    /// origin looking down -Z; set `eye`, `target` and `up` from the extrinsics.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_intrinsics(
+        fx: f32,
+        fy: f32,
+        cx: f32,
+        cy: f32,
+        width: u32,
+        height: u32,
+        znear: f32,
+        zfar: f32,
+    ) -> Self {
+        let (width, height) = (width as f32, height as f32);
+        // Like `perspective_rh`, but with the image center shifted to the principal point
+        let depth = zfar / (znear - zfar);
+        let projection = glam::Mat4::from_cols(
+            glam::vec4(2.0 * fx / width, 0.0, 0.0, 0.0),
+            glam::vec4(0.0, 2.0 * fy / height, 0.0, 0.0),
+            glam::vec4(1.0 - 2.0 * cx / width, 2.0 * cy / height - 1.0, depth, -1.0),
+            glam::vec4(0.0, 0.0, depth * znear, 0.0),
+        );
+        let camera = Camera::new(glam::Vec3::ZERO, glam::Vec3::NEG_Z, glam::Vec3::Y, 1.0, 1.0);
+        Self {
+            aspect: width / height,
+            fovy: 2.0 * (height * 0.5 / fy).atan(),
+            znear,
+            zfar,
+            projection: Some(projection),
+            ..Self::from_camera(&camera)
+        }
+    }
+
+    /// Small projector stamping its image `size` world units across onto the surface at
+    /// `position` facing `normal`, e.g. a decal placed with a click.  It looks straight at the
+    /// surface from `size` away and outweighs the projectors it overlaps, so their images don't
+    /// wash it out.  Images on walls stay upright; on floors and ceilings their top points -Z.
+    pub fn decal(position: glam::Vec3, normal: glam::Vec3, size: f32) -> Self {
+        let normal = normal.normalize();
+        let up = if normal.y.abs() > 0.99 {
+            glam::Vec3::NEG_Z
+        } else {
+            glam::Vec3::Y
+        };
+        let eye = position + normal * size;
+        let camera = Camera::new(eye, position, up, 1.0, 2.0 * 0.5_f32.atan());
+        Self {
+            znear: size * 0.5,
+            zfar: size * 2.0,
+            weight: 100.0,
+            ..Self::from_camera(&camera)
+        }
+    }
+
+    /// Fisheye projector with a circular image `fov_degrees` across filling the image height, at
+    /// the origin looking down -Z like [`Self::from_intrinsics`]
+    pub fn fisheye(fov_degrees: f32, model: Fisheye) -> Self {
+        let camera = Camera::new(glam::Vec3::ZERO, glam::Vec3::NEG_Z, glam::Vec3::Y, 1.0, 1.0);
+        Self {
+            fovy: fov_degrees.to_radians(),
+            fisheye: Some(model),
+            ..Self::from_camera(&camera)
+        }
+    }
+
+    /// Blend the image over the base color by its alpha or not, see `alpha_as_mask`
+    pub fn alpha_as_mask(mut self, enabled: bool) -> Self {
+        self.alpha_as_mask = enabled;
+        self
+    }
+
+    /// Project parallel rays covering `height` world units, see `ortho_height`
+    pub fn with_ortho_height(mut self, height: f32) -> Self {
+        self.ortho_height = Some(height);
+        self
+    }
+
+    /// Mirror the image, see `flip_horizontal` and `flip_vertical`
+    pub fn with_flip(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.flip_horizontal = horizontal;
+        self.flip_vertical = vertical;
+        self
+    }
+
+    /// Offset the principal point by `shift`, see `lens_shift`
+    pub fn with_lens_shift(mut self, shift: glam::Vec2) -> Self {
+        self.lens_shift = shift;
+        self
+    }
+
+    /// Only project inside the polygon through `vertices`, in texture coordinates of the image
+    /// with `v` pointing down, see [`MaskPolygon`]
+    pub fn with_mask_polygon(mut self, vertices: &[glam::Vec2]) -> Self {
+        self.mask_polygon = Some(MaskPolygon::new(vertices));
+        self
+    }
+
+    /// Radial (`k1`, `k2`) and tangential (`p1`, `p2`) distortion of the projector lens
+    pub fn with_distortion(mut self, k1: f32, k2: f32, p1: f32, p2: f32) -> Self {
+        self.distortion = [k1, k2, p1, p2];
+        self
+    }
+
+    /// Point the projector at the center of `mesh` and move it along its current direction
+    /// until the mesh's bounding sphere fits the image, with `margin` to spare as a fraction of
+    /// its radius, e.g. 0.1.  See [`Self::aim_at_sphere`].
+    pub fn aim_at_mesh(&mut self, mesh: &crate::model::Mesh, margin: f32) {
+        self.aim_at_sphere(mesh.bounding_sphere, margin);
+    }
+
+    /// Point the projector at `center` and move it along its current direction until the sphere
+    /// of `radius` around it fits the image, with `margin` to spare.  A projector without a
+    /// direction, its eye on its target, faces the sphere head-on from +Z.  A parallel
+    /// projection is resized to fit instead, and an off-center `projection` is fitted as if it
+    /// were centered.  The near and far planes are widened where they would cut the sphere.
+    pub fn aim_at_sphere(&mut self, (center, radius): (glam::Vec3, f32), margin: f32) {
+        let radius = radius * (1.0 + margin.max(0.0));
+        let direction = (self.target - self.eye)
+            .try_normalize()
+            .unwrap_or(glam::Vec3::NEG_Z);
+        let distance = match (self.projection, self.ortho_height) {
+            (None, Some(_)) => {
+                self.ortho_height = Some(2.0 * radius * self.aspect.recip().max(1.0));
+                2.0 * radius
+            }
+            _ => {
+                // The sphere touches the sides of the narrower field of view
+                let projection = self.projection_matrix();
+                let tan_half = projection.x_axis.x.max(projection.y_axis.y).recip();
+                radius / tan_half.atan().sin()
+            }
+        };
+        self.target = center;
+        self.eye = center - direction * distance;
+        if distance > radius {
+            self.znear = self.znear.min(distance - radius);
+        }
+        self.zfar = self.zfar.max(distance + radius);
+    }
+
+    fn projection_matrix(&self) -> glam::Mat4 {
+        // Moving the principal point by a fraction of the image moves clip space by twice that,
+        // `y` pointing up there
+        let shift = glam::Mat4::from_translation(
+            (2.0 * self.lens_shift * glam::vec2(1.0, -1.0)).extend(0.0),
+        );
+        let projection = match (self.projection, self.ortho_height) {
+            (Some(projection), _) => projection,
+            (None, None) => {
+                let fovy = match self.fisheye {
+                    Some(_) => self.fovy.min(Self::MAX_PERSPECTIVE_FOV),
+                    None => self.fovy,
+                };
+                glam::Mat4::perspective_rh(fovy, self.aspect, self.znear, self.zfar)
+            }
+            (None, Some(height)) => {
+                let half_height = height * 0.5;
+                let half_width = half_height * self.aspect;
+                glam::Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.znear,
+                    self.zfar,
+                )
+            }
+        };
+        shift * projection
+    }
+
+    fn view_matrix(&self) -> glam::Mat4 {
+        glam::Mat4::look_at_rh(self.eye, self.target, self.up)
+    }
+
+    pub fn build_view_projection_matrix(&self) -> glam::Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    /// World space corners of the volume the image is projected into: the near plane, then the
+    /// far plane, each going counter-clockwise from the bottom left of the image as seen from
+    /// the projector
+    pub fn frustum_corners(&self) -> [glam::Vec3; 8] {
+        let inv_view_proj = self.build_view_projection_matrix().inverse();
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        std::array::from_fn(|i| {
+            let (x, y) = corners[i % 4];
+            let z = (i / 4) as f32;
+            inv_view_proj.project_point3(glam::vec3(x, y, z))
+        })
+    }
+
+    /// World space planes bounding the volume the image is projected into, as `(normal, d)` with
+    /// unit normals pointing inwards: left, right, bottom, top, near and far.  Extracted from the
+    /// rows of the view projection (Gribb-Hartmann), with wgpu's depth range of `[0, 1]`.
+    pub fn frustum_planes(&self) -> [glam::Vec4; 6] {
+        let m = self.build_view_projection_matrix();
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| m.row(i));
+        [w + x, w - x, w + y, w - y, z, w - z].map(|plane| plane / plane.truncate().length())
+    }
+
+    /// Whether `world` is in the volume the image is projected into, boundaries included, e.g.
+    /// to check that geometry is covered before rendering
+    pub fn contains_point(&self, world: glam::Vec3) -> bool {
+        let point = world.extend(1.0);
+        self.frustum_planes()
+            .iter()
+            .all(|plane| plane.dot(point) >= -1e-4)
+    }
+
+    /// Outline of the area the image covers on the plane through `point` facing `normal`, e.g.
+    /// the floor of an installation: the convex polygon where the frustum's edges cross the
+    /// plane, counter-clockwise around `normal`.  Empty when the frustum doesn't reach it.
+    pub fn footprint(&self, point: glam::Vec3, normal: glam::Vec3) -> Vec<glam::Vec3> {
+        #[rustfmt::skip]
+        const EDGES: [(usize, usize); 12] = [
+            (0, 1), (1, 2), (2, 3), (3, 0),
+            (4, 5), (5, 6), (6, 7), (7, 4),
+            (0, 4), (1, 5), (2, 6), (3, 7),
+        ];
+        let normal = normal.normalize();
+        let corners = self.frustum_corners();
+        let mut outline: Vec<glam::Vec3> = EDGES
+            .iter()
+            .filter_map(|&(a, b)| {
+                let [da, db] = [corners[a], corners[b]].map(|c| (c - point).dot(normal));
+                let t = da / (da - db);
+                (da * db <= 0.0 && da != db).then(|| corners[a].lerp(corners[b], t))
+            })
+            .collect();
+        if outline.is_empty() {
+            return outline;
+        }
+        let center = outline.iter().sum::<glam::Vec3>() / outline.len() as f32;
+        let x = normal.any_orthonormal_vector();
+        let y = normal.cross(x);
+        let angle = |p: &glam::Vec3| (*p - center).dot(y).atan2((*p - center).dot(x));
+        outline.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        outline.dedup_by(|a, b| a.abs_diff_eq(*b, 1e-5));
+        outline
+    }
+
+    /// Focal lengths and principal point `[fx, fy, cx, cy]` in texture coordinates, i.e.
+    /// divided by the image size
+    fn lens(&self) -> [f32; 4] {
+        let proj = self.projection_matrix();
+        [
+            proj.x_axis.x * 0.5,
+            proj.y_axis.y * 0.5,
+            (1.0 - proj.z_axis.x) * 0.5,
+            (1.0 + proj.z_axis.y) * 0.5,
+        ]
+    }
+
+    /// Where an ideal lens would put `uv` in the image, moved to where this one puts it: the
+    /// Brown-Conrady model with `distortion`, applied to the normalized image coordinates as in
+    /// OpenCV's `projectPoints`.  The CPU reference for `distort` in the shader.
+    pub fn distort_point(&self, uv: glam::Vec2) -> glam::Vec2 {
+        if self.distortion == [0.0; 4] {
+            return uv;
+        }
+        let [k1, k2, p1, p2] = self.distortion;
+        let [fx, fy, cx, cy] = self.lens();
+        let (x, y) = ((uv.x - cx) / fx, (uv.y - cy) / fy);
+        let r2 = x * x + y * y;
+        let radial = 1.0 + k1 * r2 + k2 * r2 * r2;
+        let xd = x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+        let yd = y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+        glam::vec2(xd * fx + cx, yd * fy + cy)
+    }
+
+    /// CPU reference for the texture coordinates computed in `projected_tex_coords`: the point
+    /// is taken to projector clip space, divided by `w`, remapped to `[0, 1]` with `v` pointing
+    /// down, distorted and flipped.  A fisheye projector maps its direction instead, see
+    /// `fisheye_tex_coords`.  Returns `None` for points outside the image or its mask polygon,
+    /// or behind the projector if `reject_behind` is set.
+    pub fn project_point(&self, world: glam::Vec3) -> Option<glam::Vec2> {
+        let tex_coords = match self.fisheye {
+            Some(model) => {
+                let view = self.view_matrix().transform_point3(world);
+                self.fisheye_tex_coords(model, view)
+            }
+            None => {
+                let clip = self.build_view_projection_matrix() * world.extend(1.0);
+                clip_to_tex_coords(clip, self.reject_behind).map(|uv| self.distort_point(uv))
+            }
+        };
+        tex_coords
+            .map(|uv| self.flip_point(uv))
+            .filter(is_in_image)
+            .filter(|&uv| self.mask_polygon.is_none_or(|mask| mask.contains(uv)))
+    }
+
+    /// CPU reference for `fisheye_tex_coords` in the shader: where the direction to `view`, a
+    /// point in projector view space, lands in the image circle around the principal point.
+    /// `None` past half the field of view.
+    fn fisheye_tex_coords(&self, model: Fisheye, view: glam::Vec3) -> Option<glam::Vec2> {
+        let direction = view.try_normalize()?;
+        let theta = (-direction.z).clamp(-1.0, 1.0).acos();
+        let half_fov = self.fovy * 0.5;
+        if theta > half_fov {
+            return None;
+        }
+        let radius = 0.5 * model.radius(theta, half_fov);
+        let [fx, fy, cx, cy] = self.lens();
+        let around = direction.truncate().normalize_or_zero() * glam::vec2(fx / fy, -1.0);
+        Some(glam::vec2(cx, cy) + radius * around)
+    }
+
+    /// Mirror texture coordinates `uv` as the image is flipped
+    fn flip_point(&self, uv: glam::Vec2) -> glam::Vec2 {
+        let flip = glam::BVec2::new(self.flip_horizontal, self.flip_vertical);
+        glam::Vec2::select(flip, 1.0 - uv, uv)
+    }
+}
+
+/// Perspective divide and remap of a clip space position to texture coordinates, `None` with
+/// `reject_behind` behind the eye
+fn clip_to_tex_coords(clip: glam::Vec4, reject_behind: bool) -> Option<glam::Vec2> {
+    if clip.w == 0.0 || (reject_behind && clip.w < 0.0) {
+        return None;
+    }
+    let ndc = clip.truncate() / clip.w;
+    let tex_pos = ndc * 0.5 + 0.5;
+    Some(glam::vec2(tex_pos.x, 1.0 - tex_pos.y))
+}
+
+/// `wgpu::Color` as `[r, g, b, a]`, since wgpu only implements serde for its API traces
+mod serde_color {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &wgpu::Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<wgpu::Color, D::Error> {
+        let [r, g, b, a] = <[f64; 4]>::deserialize(deserializer)?;
+        Ok(wgpu::Color { r, g, b, a })
+    }
+}
+
+fn is_in_image(uv: &glam::Vec2) -> bool {
+    (0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y)
+}
+
+/// Turns a projector from its pose at the start to `rotation` over `duration` seconds, moving
+/// its eye in a straight line to `eye` at the same time, see [`Projector::slerp_to`]
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectorAnimation {
+    start: Projector,
+    pub rotation: glam::Quat,
+    pub eye: glam::Vec3,
+    pub duration: f32,
+    elapsed: f32,
+}
+
+impl ProjectorAnimation {
+    pub fn new(start: Projector, rotation: glam::Quat, eye: glam::Vec3, duration: f32) -> Self {
+        Self {
+            start,
+            rotation,
+            eye,
+            duration: duration.max(f32::EPSILON),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Move on by `dt` seconds and return the projector's pose there
+    pub fn advance(&mut self, dt: f32) -> Projector {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        let t = self.elapsed / self.duration;
+        let projector = self.start.slerp_to(self.rotation, t);
+        projector.with_rotation_translation(projector.rotation(), self.start.eye.lerp(self.eye, t))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// What scrolling does, see [`CameraController::zoom_mode`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZoomMode {
+    /// Move the eye towards or away from the target
+    #[default]
+    Dolly,
+    /// Narrow or widen the field of view, keeping the eye in place so the view still matches
+    /// the projector's
+    Fov,
+}
+
 pub struct CameraController {
+    /// Orbit offset per frame at [`CameraController::FRAME_RATE`]
     pub speed: f32,
+    /// Fraction of the previous frame's velocity retained each frame at
+    /// [`CameraController::FRAME_RATE`], in `[0, 1)`.  Zero applies input instantaneously.
+    pub damping: f32,
     pub is_fwd_pressed: bool,
     pub is_bwd_pressed: bool,
     pub is_rt_pressed: bool,
     pub is_lt_pressed: bool,
+    pub is_up_pressed: bool,
+    pub is_down_pressed: bool,
+    /// Current velocity: orbiting `x` around the up axis and `y` towards it, and rising `z`
+    /// along it
+    velocity: glam::Vec3,
+    /// Move a fixed `step_angle` per key press instead of while keys are held, for repeatable
+    /// camera placement.  Key repeat is ignored.
+    pub step_mode: bool,
+    /// Orbit angle of one step in radians
+    pub step_angle: f32,
+    /// Steps pressed since the last update, in the same axes as `velocity`
+    pending_steps: glam::Vec3,
+    /// Orbit angle per pixel of mouse motion, in radians
+    pub sensitivity: f32,
+    /// Mouse motion in pixels since the last update, see [`CameraController::process_mouse`]
+    mouse_delta: glam::Vec2,
+    pub bindings: KeyBindings,
+    pub zoom_mode: ZoomMode,
+    /// Narrowest field of view [`ZoomMode::Fov`] zooms to, in radians
+    pub min_fovy: f32,
+    /// Widest field of view [`ZoomMode::Fov`] zooms to, in radians, below the half turn where
+    /// the view would flip over
+    pub max_fovy: f32,
+    /// Scroll lines since the last update, positive zooming in
+    scroll: f32,
+    /// Field of view [`ZoomMode::Fov`] is easing towards
+    target_fovy: Option<f32>,
 }
 
 impl CameraController {
+    /// Frames per second `speed` and `damping` are given for
+    pub const FRAME_RATE: f32 = 60.0;
+    /// Most frames one update moves by, so the first update after idling doesn't jump
+    const MAX_FRAMES: f32 = 4.0;
+    /// Factor each scroll line zooming in scales the distance or field of view by
+    const ZOOM_STEP: f32 = 0.9;
+    /// Fraction of the remaining field of view change left after each frame
+    const ZOOM_DAMPING: f32 = 0.7;
+
     pub fn new(speed: f32) -> Self {
         Self {
             speed,
+            damping: 0.0,
             is_fwd_pressed: false,
             is_bwd_pressed: false,
             is_rt_pressed: false,
             is_lt_pressed: false,
+            is_up_pressed: false,
+            is_down_pressed: false,
+            velocity: glam::Vec3::ZERO,
+            step_mode: false,
+            step_angle: 5_f32.to_radians(),
+            pending_steps: glam::Vec3::ZERO,
+            sensitivity: 0.005,
+            mouse_delta: glam::Vec2::ZERO,
+            bindings: KeyBindings::default(),
+            zoom_mode: ZoomMode::Dolly,
+            min_fovy: 5_f32.to_radians(),
+            max_fovy: 120_f32.to_radians(),
+            scroll: 0.0,
+            target_fovy: None,
+        }
+    }
+
+    pub fn with_zoom_mode(mut self, zoom_mode: ZoomMode) -> Self {
+        self.zoom_mode = zoom_mode;
+        self
+    }
+
+    /// Move with the keys of `bindings` instead of ESDF
+    pub fn with_bindings(mut self, bindings: KeyBindings) -> Self {
+        self.bindings = bindings;
+        self
+    }
+
+    /// Enable [`CameraController::step_mode`] with steps of `step_angle` radians
+    pub fn with_step_mode(mut self, step_angle: f32) -> Self {
+        self.step_mode = true;
+        self.step_angle = step_angle;
+        self
+    }
+
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Release all keys and stop any residual motion
+    pub fn reset(&mut self) {
+        self.is_fwd_pressed = false;
+        self.is_bwd_pressed = false;
+        self.is_rt_pressed = false;
+        self.is_lt_pressed = false;
+        self.is_up_pressed = false;
+        self.is_down_pressed = false;
+        self.velocity = glam::Vec3::ZERO;
+        self.pending_steps = glam::Vec3::ZERO;
+        self.mouse_delta = glam::Vec2::ZERO;
+        self.scroll = 0.0;
+        self.target_fovy = None;
+    }
+
+    /// Whether the next `update_camera` will move the camera
+    pub fn is_moving(&self) -> bool {
+        if self.scroll != 0.0 || self.target_fovy.is_some() {
+            return true;
         }
+        if self.step_mode {
+            return self.velocity != glam::Vec3::ZERO || self.pending_steps != glam::Vec3::ZERO;
+        }
+        self.velocity != glam::Vec3::ZERO
+            || self.is_fwd_pressed
+            || self.is_bwd_pressed
+            || self.is_rt_pressed
+            || self.is_lt_pressed
+            || self.is_up_pressed
+            || self.is_down_pressed
+    }
+
+    /// Length of the current velocity, zero once damping has let it die down
+    pub fn current_speed(&self) -> f32 {
+        self.velocity.length()
     }
 
     pub fn process_events(&mut self, event: &WindowEvent) -> bool {
         match event {
             WindowEvent::KeyboardInput { event, .. } => {
                 let is_pressed = event.state.is_pressed();
-                match event.physical_key {
-                    PhysicalKey::Code(KeyCode::KeyE) => {
-                        self.is_fwd_pressed = is_pressed;
-                        true
-                    }
-                    PhysicalKey::Code(KeyCode::KeyD) => {
-                        self.is_bwd_pressed = is_pressed;
-                        true
-                    }
-                    PhysicalKey::Code(KeyCode::KeyF) => {
-                        self.is_rt_pressed = is_pressed;
-                        true
-                    }
-                    PhysicalKey::Code(KeyCode::KeyS) => {
-                        self.is_lt_pressed = is_pressed;
-                        true
-                    }
-                    _ => false,
+                let movement = match event.physical_key {
+                    PhysicalKey::Code(key) => self.bindings.movement(key),
+                    _ => None,
+                };
+                let (was_pressed, step) = match movement {
+                    Some(Movement::Forward) => (
+                        std::mem::replace(&mut self.is_fwd_pressed, is_pressed),
+                        glam::Vec3::Y,
+                    ),
+                    Some(Movement::Backward) => (
+                        std::mem::replace(&mut self.is_bwd_pressed, is_pressed),
+                        glam::Vec3::NEG_Y,
+                    ),
+                    Some(Movement::Right) => (
+                        std::mem::replace(&mut self.is_rt_pressed, is_pressed),
+                        glam::Vec3::X,
+                    ),
+                    Some(Movement::Left) => (
+                        std::mem::replace(&mut self.is_lt_pressed, is_pressed),
+                        glam::Vec3::NEG_X,
+                    ),
+                    Some(Movement::Up) => (
+                        std::mem::replace(&mut self.is_up_pressed, is_pressed),
+                        glam::Vec3::Z,
+                    ),
+                    Some(Movement::Down) => (
+                        std::mem::replace(&mut self.is_down_pressed, is_pressed),
+                        glam::Vec3::NEG_Z,
+                    ),
+                    None => return false,
+                };
+                if self.step_mode && is_pressed && !was_pressed {
+                    self.pending_steps += step;
                 }
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.process_scroll(scroll_lines(delta));
+                true
             }
             _ => false,
         }
     }
 
-    pub fn update_camera(&self, camera: &mut Camera) {
+    /// Zoom in by `lines` scroll lines, or out if negative, on the next update
+    pub fn process_scroll(&mut self, lines: f32) {
+        self.scroll += lines;
+    }
+
+    /// Move `camera` by the motion of the `dt` since the last update, so the speed doesn't
+    /// depend on the frame rate
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: std::time::Duration) {
+        let frames = (dt.as_secs_f32() * Self::FRAME_RATE).min(Self::MAX_FRAMES);
+        self.zoom(camera, frames);
+
+        // Dragging turns the scene as if grabbing it, so the eye moves the opposite way
+        let drag = std::mem::take(&mut self.mouse_delta) * self.sensitivity;
+        if drag != glam::Vec2::ZERO {
+            let offset = Self::angle_offset(camera, drag * glam::vec2(-1.0, 1.0));
+            Self::orbit(camera, offset, 0.0);
+        }
+
+        if self.step_mode {
+            let steps = std::mem::take(&mut self.pending_steps);
+            self.velocity = glam::Vec3::ZERO;
+            // A step up moves as far as a step sideways
+            let offset = Self::angle_offset(camera, steps.truncate() * self.step_angle);
+            let rise = (steps.z * self.step_angle).tan() * camera.eye.distance(camera.target);
+            Self::orbit(camera, offset, 0.0);
+            Self::rise(camera, rise);
+            return;
+        }
+
+        let axis = |pos: bool, neg: bool| pos as i32 as f32 - neg as i32 as f32;
+        let target_velocity = glam::vec3(
+            axis(self.is_rt_pressed, self.is_lt_pressed),
+            axis(self.is_fwd_pressed, self.is_bwd_pressed),
+            axis(self.is_up_pressed, self.is_down_pressed),
+        ) * self.speed;
+        self.velocity = target_velocity.lerp(self.velocity, self.damping.powf(frames));
+        if self.current_speed() < 1e-4 {
+            self.velocity = glam::Vec3::ZERO;
+        }
+        Self::orbit(camera, self.velocity.truncate() * frames, self.speed);
+        Self::rise(camera, self.velocity.z * frames);
+    }
+
+    /// Apply the scrolling since the last update.  Dollying is immediate, while a field of view
+    /// change eases in over the following frames.
+    fn zoom(&mut self, camera: &mut Camera, frames: f32) {
+        let scale = Self::ZOOM_STEP.powf(std::mem::take(&mut self.scroll));
+        match self.zoom_mode {
+            ZoomMode::Dolly if scale != 1.0 => {
+                let offset = camera.eye - camera.target;
+                let distance = (offset.length() * scale).max(camera.znear);
+                camera.eye = camera.target + offset.normalize() * distance;
+            }
+            ZoomMode::Dolly => {}
+            ZoomMode::Fov => {
+                let max_fovy = self.max_fovy.min(std::f32::consts::PI - 1e-3);
+                if scale != 1.0 {
+                    let fovy = self.target_fovy.unwrap_or(camera.fovy) * scale;
+                    self.target_fovy = Some(fovy.clamp(self.min_fovy, max_fovy));
+                }
+                if let Some(target) = self.target_fovy {
+                    let remaining = Self::ZOOM_DAMPING.powf(frames);
+                    camera.fovy = target + (camera.fovy - target) * remaining;
+                    if (camera.fovy - target).abs() < 1e-4 {
+                        camera.fovy = target;
+                        self.target_fovy = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Orbit by the mouse moving `delta` pixels, applied on the next update.  Only called while
+    /// the view is being dragged.
+    pub fn process_mouse(&mut self, delta: glam::Vec2) {
+        self.mouse_delta += delta;
+    }
+
+    /// The `orbit` offset turning the eye by `angles`.  Offsetting the eye by `tan(angle)` times
+    /// its distance turns it by exactly `angle` sideways and close to it vertically, where the
+    /// up axis isn't square to the view.
+    fn angle_offset(camera: &Camera, angles: glam::Vec2) -> glam::Vec2 {
+        glam::vec2(angles.x.tan(), angles.y.tan()) * camera.eye.distance(camera.target)
+    }
+
+    /// Swing the eye around the target by `offset` (sideways, then towards the up axis), keeping
+    /// its distance.  The vertical part is skipped within `min_distance` of the target.
+    fn orbit(camera: &mut Camera, offset: glam::Vec2, min_distance: f32) {
         let fwd = camera.target - camera.eye;
         let fwd_norm = fwd.normalize();
         let fwd_mag = fwd.length();
 
-        if self.is_fwd_pressed && fwd_mag > self.speed {
-            //camera.eye += fwd_norm * self.speed;
-            camera.eye = camera.target - (fwd - camera.up * self.speed).normalize() * fwd_mag;
-        }
-        if self.is_bwd_pressed && fwd_mag > self.speed {
-            //camera.eye -= fwd_norm * self.speed;
-            camera.eye = camera.target - (fwd + camera.up * self.speed).normalize() * fwd_mag;
+        if offset.y != 0.0 && fwd_mag > min_distance {
+            camera.eye = camera.target - (fwd - camera.up * offset.y).normalize() * fwd_mag;
         }
 
         let right = fwd_norm.cross(camera.up);
@@ -127,19 +1155,134 @@ impl CameraController {
         let fwd = camera.target - camera.eye;
         let fwd_mag = fwd.length();
 
-        if self.is_rt_pressed {
-            camera.eye = camera.target - (fwd - right * self.speed).normalize() * fwd_mag;
+        if offset.x != 0.0 {
+            camera.eye = camera.target - (fwd - right * offset.x).normalize() * fwd_mag;
         }
-        if self.is_lt_pressed {
-            camera.eye = camera.target - (fwd + right * self.speed).normalize() * fwd_mag;
+    }
+
+    /// Move the eye and the target `distance` along the up axis, keeping the view direction
+    fn rise(camera: &mut Camera, distance: f32) {
+        let offset = camera.up.normalize_or_zero() * distance;
+        camera.eye += offset;
+        camera.target += offset;
+    }
+}
+
+/// Scroll lines in `delta`, positive away from the user
+fn scroll_lines(delta: &MouseScrollDelta) -> f32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, lines) => *lines,
+        // Roughly one line per notch of a typical wheel
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+    }
+}
+
+/// Viewpoint on a sphere around `target` with +Y up, for turning an object around in a viewer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitCamera {
+    pub target: glam::Vec3,
+    pub distance: f32,
+    /// Angle around +Y in radians, zero looking from +Z and a quarter turn from +X
+    pub yaw: f32,
+    /// Angle above the horizontal plane through `target` in radians
+    pub pitch: f32,
+}
+
+impl OrbitCamera {
+    /// The orbit `camera` is on, looking at its target
+    pub fn from_camera(camera: &Camera) -> Self {
+        let offset = camera.eye - camera.target;
+        let distance = offset.length();
+        Self {
+            target: camera.target,
+            distance,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / distance).clamp(-1.0, 1.0).asin(),
         }
     }
+
+    pub fn eye(&self) -> glam::Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        self.target
+            + glam::vec3(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw) * self.distance
+    }
+
+    /// Move `camera` onto the orbit, keeping its lens
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye();
+        camera.target = self.target;
+        camera.up = glam::Vec3::Y;
+    }
+}
+
+/// Turns an [`OrbitCamera`] by dragging and moves it in and out by scrolling.  Unlike
+/// [`CameraController`] the pitch stops short of the poles, so the view never flips over.
+pub struct OrbitController {
+    /// Radians per pixel dragged
+    pub sensitivity: f32,
+    /// Factor each scroll line zooming in scales the distance by
+    pub zoom_step: f32,
+    drag: glam::Vec2,
+    scroll: f32,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.005,
+            zoom_step: 0.9,
+            drag: glam::Vec2::ZERO,
+            scroll: 0.0,
+        }
+    }
+}
+
+impl OrbitController {
+    /// Furthest the pitch goes towards either pole
+    const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+    /// Turn by dragging `delta` pixels on the next update
+    pub fn process_mouse(&mut self, delta: glam::Vec2) {
+        self.drag += delta;
+    }
+
+    /// Handle scrolling, returning whether `event` was used
+    pub fn process_events(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += scroll_lines(delta);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the next `update` will move the orbit
+    pub fn is_moving(&self) -> bool {
+        self.drag != glam::Vec2::ZERO || self.scroll != 0.0
+    }
+
+    /// Apply the dragging and scrolling since the last update to `orbit`, keeping it at least
+    /// `min_distance`, e.g. the near plane, from the target
+    pub fn update(&mut self, orbit: &mut OrbitCamera, min_distance: f32) {
+        // Dragging turns the scene as if grabbing it, so the eye moves the opposite way
+        let drag = std::mem::take(&mut self.drag) * self.sensitivity;
+        orbit.yaw -= drag.x;
+        orbit.pitch = (orbit.pitch + drag.y).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+        let scale = self.zoom_step.powf(std::mem::take(&mut self.scroll));
+        orbit.distance = (orbit.distance * scale).max(min_distance);
+    }
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     pub view_proj: [[f32; 4]; 4],
+    /// World space eye position.  `w` is padding: a WGSL `vec3` is 16-byte aligned in uniform
+    /// buffers, so a `vec4` keeps the Rust and shader layouts identical.
+    pub view_pos: [f32; 4],
+    pub inv_view_proj: [[f32; 4]; 4],
 }
 
 impl CameraUniform {
@@ -154,16 +1297,797 @@ impl CameraUniform {
 
         Self {
             view_proj,
+            view_pos: [0.0, 0.0, 0.0, 1.0],
+            inv_view_proj: view_proj,
         }
     }
 
+    /// Degenerate cameras (e.g. zero aspect or coincident eye and target) produce a non-finite
+    /// or singular matrix; in that case the last good matrix is kept so the frame still renders.
     pub fn update_view_proj(&mut self, camera: &Camera) {
-        let matrix = camera.build_view_projection_matrix();
-        self.view_proj = [
-            matrix.x_axis.into(),
-            matrix.y_axis.into(),
-            matrix.z_axis.into(),
-            matrix.w_axis.into(),
-        ];
+        self.set_view_proj(camera.build_view_projection_matrix(), camera.eye);
+    }
+
+    /// See through `projector` instead, e.g. to preview what it covers.  Its lens distortion is
+    /// left out.
+    pub fn update_from_projector(&mut self, projector: &Projector) {
+        self.set_view_proj(projector.build_view_projection_matrix(), projector.eye);
+    }
+
+    /// Take on a view projection `matrix` seen from `eye` as is, e.g. one zoomed into a pixel
+    pub fn set_view_proj(&mut self, matrix: glam::Mat4, eye: glam::Vec3) {
+        let inverse = matrix.inverse();
+        if !matrix.is_finite() || !inverse.is_finite() {
+            log::warn!("Degenerate view projection, keeping previous: {:?}", matrix);
+            return;
+        }
+        self.view_proj = matrix.to_cols_array_2d();
+        self.view_pos = eye.extend(1.0).into();
+        self.inv_view_proj = inverse.to_cols_array_2d();
+    }
+}
+
+/// What the shaders need to know about the [`Projector`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ProjectorUniform {
+    pub view_proj: [[f32; 4]; 4],
+    pub base_color: [f32; 4],
+    /// A `bool` as `u32`
+    pub reject_behind: u32,
+    pub weight: f32,
+    /// Horizontal and vertical flip as `u32`s, which also aligns `distortion` to 16 bytes like
+    /// WGSL does
+    pub flip: [u32; 2],
+    pub distortion: [f32; 4],
+    /// See `Projector::lens`
+    pub lens: [f32; 4],
+    /// 0 for a perspective projector, otherwise 1 + the [`Fisheye`] model.  `view_proj` then
+    /// only takes points to projector view space.
+    pub fisheye: u32,
+    pub half_fov: f32,
+    /// A `bool` as `u32`
+    pub alpha_as_mask: u32,
+    /// Number of vertices in `mask`, 0 without a mask polygon
+    pub mask_len: u32,
+    /// [`MaskPolygon`] vertices two to a `vec4`, which keeps the array stride WGSL requires in
+    /// uniform buffers
+    pub mask: [[f32; 4]; MaskPolygon::MAX_VERTICES / 2],
+}
+
+impl ProjectorUniform {
+    /// Take on `projector`, keeping the last good matrix if its view projection is degenerate,
+    /// like [`CameraUniform::update_view_proj`]
+    pub fn update(&mut self, projector: &Projector) {
+        let view_proj = self.view_proj;
+        *self = projector.into();
+        if !glam::Mat4::from_cols_array_2d(&self.view_proj).is_finite() {
+            log::warn!(
+                "Non-finite projector view projection, keeping previous: {:?}",
+                self.view_proj
+            );
+            self.view_proj = view_proj;
+        }
+    }
+}
+
+impl From<&Projector> for ProjectorUniform {
+    fn from(projector: &Projector) -> Self {
+        let color = projector.base_color;
+        let mask = projector
+            .mask_polygon
+            .as_ref()
+            .map_or(&[][..], MaskPolygon::vertices);
+        let view_proj = match projector.fisheye {
+            Some(_) => projector.view_matrix(),
+            None => projector.build_view_projection_matrix(),
+        };
+        Self {
+            view_proj: view_proj.to_cols_array_2d(),
+            base_color: [color.r, color.g, color.b, color.a].map(|c| c as f32),
+            reject_behind: projector.reject_behind as u32,
+            weight: projector.weight,
+            flip: [projector.flip_horizontal, projector.flip_vertical].map(u32::from),
+            distortion: projector.distortion,
+            lens: projector.lens(),
+            fisheye: projector.fisheye.map_or(0, |model| 1 + model as u32),
+            half_fov: projector.fovy * 0.5,
+            alpha_as_mask: projector.alpha_as_mask as u32,
+            mask_len: mask.len() as u32,
+            mask: std::array::from_fn(|i| {
+                let pair = |j| mask.get(j).copied().unwrap_or_default();
+                let (a, b): (glam::Vec2, glam::Vec2) = (pair(2 * i), pair(2 * i + 1));
+                [a.x, a.y, b.x, b.y]
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default viewpoint, looking down at the origin
+    fn camera() -> Camera {
+        Camera::new([-6.0, 6.0, 6.0], [0.0, 0.0, 0.0], glam::Vec3::Y, 1.0, 0.5)
+    }
+
+    /// Remapping forward to W frees up E, which moves forward by default
+    #[test]
+    fn remapped_key_replaces_default() {
+        let bindings = KeyBindings {
+            forward: KeyCode::KeyW,
+            ..Default::default()
+        };
+        assert_eq!(bindings.movement(KeyCode::KeyW), Some(Movement::Forward));
+        assert_eq!(bindings.movement(KeyCode::KeyE), None);
+    }
+
+    /// Both layouts bind raising and lowering the camera, R and W by default and E and Q for WASD
+    #[test]
+    fn up_and_down_are_bound() {
+        for (bindings, up, down) in [
+            (KeyBindings::default(), KeyCode::KeyR, KeyCode::KeyW),
+            (KeyBindings::wasd(), KeyCode::KeyE, KeyCode::KeyQ),
+        ] {
+            assert_eq!(bindings.movement(up), Some(Movement::Up), "{bindings:?}");
+            assert_eq!(
+                bindings.movement(down),
+                Some(Movement::Down),
+                "{bindings:?}"
+            );
+        }
+    }
+
+    /// An off-center calibrated projector puts its optical axis at the principal point
+    #[test]
+    fn intrinsics_principal_point() {
+        let (width, height) = (640, 480);
+        let principal_point = glam::vec2(300.0, 260.0);
+        let projector = Projector::from_intrinsics(
+            500.0,
+            520.0,
+            principal_point.x,
+            principal_point.y,
+            width,
+            height,
+            0.1,
+            100.0,
+        );
+        let pixel = projector
+            .project_point(glam::vec3(0.0, 0.0, -5.0))
+            .expect("Optical axis is not projected")
+            * glam::vec2(width as f32, height as f32);
+        assert!(
+            pixel.abs_diff_eq(principal_point, 1e-3),
+            "Optical axis projects to pixel {pixel} instead of the principal point \
+             {principal_point}"
+        );
+    }
+
+    /// Up and down move the eye and the target together, so the view direction doesn't change
+    #[test]
+    fn rising_keeps_view_direction() {
+        let mut camera = camera();
+        let direction = camera.target - camera.eye;
+        let mut controller = CameraController::new(0.2);
+        controller.is_up_pressed = true;
+        controller.update_camera(&mut camera, std::time::Duration::from_secs_f32(1.0 / 60.0));
+        let expected = glam::vec3(-6.0, 6.2, 6.0);
+        assert!(
+            camera.eye.abs_diff_eq(expected, 1e-5),
+            "Eye rose to {} instead of {expected}",
+            camera.eye
+        );
+        assert!((camera.target - camera.eye).abs_diff_eq(direction, 1e-5));
+    }
+
+    /// With damping the camera keeps moving after the key is released, slower every frame
+    /// until it comes to rest
+    #[test]
+    fn damped_velocity_decays() {
+        let mut camera = camera();
+        let mut controller = CameraController::new(0.2).with_damping(0.8);
+        let frame = std::time::Duration::from_secs_f32(1.0 / CameraController::FRAME_RATE);
+        controller.is_rt_pressed = true;
+        for _ in 0..30 {
+            controller.update_camera(&mut camera, frame);
+        }
+        controller.is_rt_pressed = false;
+        let mut speed = controller.current_speed();
+        let mut frames = 0;
+        while controller.is_moving() {
+            controller.update_camera(&mut camera, frame);
+            let slower = controller.current_speed();
+            assert!(slower < speed, "Speed rose from {speed} to {slower}");
+            speed = slower;
+            frames += 1;
+            assert!(
+                frames < 100,
+                "Still moving at {speed} after {frames} frames"
+            );
+        }
+        assert!(
+            frames > 1,
+            "Stopped after {frames} frames despite the damping"
+        );
+        assert_eq!(speed, 0.0, "Speed at rest");
+    }
+
+    /// Without damping the camera stops on the first update after the key is released
+    #[test]
+    fn undamped_stops_immediately() {
+        let mut camera = camera();
+        let mut controller = CameraController::new(0.2);
+        let frame = std::time::Duration::from_secs_f32(1.0 / CameraController::FRAME_RATE);
+        controller.is_rt_pressed = true;
+        controller.update_camera(&mut camera, frame);
+        controller.is_rt_pressed = false;
+        let eye = camera.eye;
+        controller.update_camera(&mut camera, frame);
+        assert_eq!(controller.current_speed(), 0.0, "Speed after release");
+        assert_eq!(camera.eye, eye, "Eye moved after release");
+        assert!(!controller.is_moving(), "Still moving after release");
+    }
+
+    /// Zooming the field of view far in or out eases to the limits and stops there
+    #[test]
+    fn fov_zoom_stops_at_limits() {
+        let mut camera = camera();
+        let mut controller = CameraController::new(0.2).with_zoom_mode(ZoomMode::Fov);
+        let frame = std::time::Duration::from_secs_f32(1.0 / CameraController::FRAME_RATE);
+        for (lines, limit) in [(100.0, controller.min_fovy), (-100.0, controller.max_fovy)] {
+            controller.process_scroll(lines);
+            let mut frames = 0;
+            while controller.is_moving() {
+                controller.update_camera(&mut camera, frame);
+                frames += 1;
+                assert!(frames < 1000, "Zoom is still easing after {frames} frames");
+            }
+            assert_eq!(camera.fovy, limit, "Zoomed by {lines} lines");
+        }
+    }
+
+    /// A quarter turn of yaw swings the eye from +Z to +X of the target, which stays in the
+    /// middle of the view
+    #[test]
+    fn orbit_quarter_turn() {
+        let mut orbit = OrbitCamera {
+            target: glam::vec3(1.0, 2.0, 3.0),
+            distance: 5.0,
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        for (yaw, offset) in [(0.0, glam::Vec3::Z), (90_f32.to_radians(), glam::Vec3::X)] {
+            orbit.yaw = yaw;
+            let expected = orbit.target + offset * orbit.distance;
+            assert!(
+                orbit.eye().abs_diff_eq(expected, 1e-5),
+                "Orbit eye at yaw {yaw} is {} instead of {expected}",
+                orbit.eye()
+            );
+            let mut camera = camera();
+            orbit.apply(&mut camera);
+            let target = camera
+                .project_point(orbit.target)
+                .expect("Orbit target is out of view");
+            assert!(
+                target.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+                "Orbit target is at {target} in the view instead of the center"
+            );
+        }
+    }
+
+    /// A camera saved and loaded again has the same view projection
+    #[test]
+    fn camera_file_round_trip() {
+        let camera = camera();
+        // Unique per process so concurrent test runs don't overwrite each other's file
+        let file_name = format!("image-projection-test-camera-{}.ron", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        camera.save(&path).unwrap();
+        let loaded = Camera::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (expected, loaded) = (
+            camera.build_view_projection_matrix(),
+            loaded.unwrap().build_view_projection_matrix(),
+        );
+        assert!(
+            loaded.abs_diff_eq(expected, 1e-6),
+            "Loaded camera has view projection {loaded} instead of {expected}"
+        );
+    }
+
+    /// The near corners of a symmetric frustum are closer to the projector than the far ones
+    #[test]
+    fn frustum_near_corners_are_closer() {
+        let projector = Projector::from_camera(&camera());
+        let corners = projector.frustum_corners();
+        let (near, far) = corners.split_at(4);
+        for (i, (near, far)) in near.iter().zip(far).enumerate() {
+            let (near, far) = (near.distance(projector.eye), far.distance(projector.eye));
+            assert!(
+                near < far,
+                "Frustum corner {i} is {near} from the projector on the near plane and {far} on \
+                 the far plane"
+            );
+        }
+    }
+
+    /// The projector target lands in the middle of the projected image
+    #[test]
+    fn projector_target_is_image_center() {
+        let projector = Projector::from_camera(&camera());
+        let center = projector
+            .project_point(projector.target)
+            .expect("Projector target is not projected");
+        assert!(
+            center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+            "Projector target projects to {center} instead of the image center"
+        );
+    }
+
+    /// On a surface running through the projector, the half in front receives the image and the
+    /// half behind only gets the mirrored image once `reject_behind` is turned off
+    #[test]
+    fn surface_straddling_projector() {
+        let mut projector = Projector::from_camera(&camera());
+        let forward = (projector.target - projector.eye).normalize();
+        let side = forward.cross(projector.up).normalize();
+        for offset in [-0.2, 0.0, 0.2] {
+            let front = projector.eye + forward * 2.0 + side * offset;
+            let behind = projector.eye - forward * 2.0 + side * offset;
+            projector.reject_behind = true;
+            assert!(
+                projector.project_point(front).is_some(),
+                "Point {front} in front of the projector is not projected"
+            );
+            assert!(
+                projector.project_point(behind).is_none(),
+                "Point {behind} behind the projector is projected"
+            );
+            projector.reject_behind = false;
+            assert!(
+                projector.project_point(behind).is_some(),
+                "Point {behind} behind the projector is not mirrored without reject_behind"
+            );
+        }
+    }
+
+    /// The ray through the middle of the view points straight at the target
+    #[test]
+    fn screen_ray_through_center() {
+        let camera = camera();
+        let viewport = glam::vec2(64.0, 48.0);
+        let (_, dir) = camera.screen_ray(viewport * 0.5, viewport);
+        let expected = (camera.target - camera.eye).normalize();
+        assert!(
+            dir.abs_diff_eq(expected, 1e-5),
+            "Ray through the center of the view points along {dir} instead of {expected}"
+        );
+    }
+
+    /// Resetting in the middle of a field of view zoom leaves the field of view where it is
+    #[test]
+    fn reset_stops_fov_zoom() {
+        let mut camera = camera();
+        let mut controller = CameraController::new(0.2).with_zoom_mode(ZoomMode::Fov);
+        let frame = std::time::Duration::from_secs_f32(1.0 / CameraController::FRAME_RATE);
+        controller.process_scroll(5.0);
+        controller.update_camera(&mut camera, frame);
+        controller.reset();
+        assert!(!controller.is_moving(), "Still moving after reset");
+        let fovy = camera.fovy;
+        controller.update_camera(&mut camera, frame);
+        assert_eq!(camera.fovy, fovy, "Field of view after reset");
+    }
+
+    /// An infinitely wide view flattens the projection to a finite matrix without an inverse,
+    /// which the uniform doesn't take
+    #[test]
+    fn singular_view_projection_is_skipped() {
+        let mut camera = camera();
+        let mut uniform = CameraUniform::new();
+        uniform.update_view_proj(&camera);
+        let previous = uniform.view_proj;
+        camera.aspect = f32::INFINITY;
+        assert!(camera.build_view_projection_matrix().is_finite());
+        uniform.update_view_proj(&camera);
+        assert_eq!(uniform.view_proj, previous, "Singular matrix uploaded");
+    }
+
+    /// A projector with its eye on its target keeps projecting the last good image
+    #[test]
+    fn degenerate_projector_is_skipped() {
+        let mut projector = Projector::from_camera(&camera());
+        let mut uniform = ProjectorUniform::from(&projector);
+        let previous = uniform.view_proj;
+        projector.eye = projector.target;
+        uniform.update(&projector);
+        assert_eq!(uniform.view_proj, previous, "Non-finite matrix uploaded");
+    }
+
+    /// After aiming at a cube, every corner is in the image and between the near and far planes,
+    /// seen from the direction the projector had before
+    #[test]
+    fn aimed_projector_covers_cube() {
+        let corners: Vec<_> = (0..8)
+            .map(|corner| {
+                let [x, y, z] = [1, 2, 4].map(|bit| if corner & bit != 0 { 1.0 } else { -1.0 });
+                glam::vec3(x, y, z)
+            })
+            .collect();
+        let mut projector = Projector {
+            target: glam::vec3(5.0, 0.0, 0.0),
+            aspect: 2.0,
+            ..Projector::from_camera(&camera())
+        };
+        let direction = (projector.target - projector.eye).normalize();
+        projector.aim_at_sphere(crate::model::bounding_sphere(&corners), 0.1);
+        let aimed = (projector.target - projector.eye).normalize();
+        assert!(
+            aimed.abs_diff_eq(direction, 1e-5),
+            "Aimed along {aimed} instead of {direction}"
+        );
+        let view_proj = projector.build_view_projection_matrix();
+        for corner in corners {
+            let ndc = view_proj.project_point3(corner);
+            let inside = ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (0.0..=1.0).contains(&ndc.z);
+            assert!(inside, "Corner {corner} is at {ndc} in projector NDC");
+        }
+    }
+
+    /// An orthographic view shows a square facing the camera at the same size whether it is near
+    /// or far, where the perspective view shrinks the far one
+    #[test]
+    fn ortho_size_ignores_depth() {
+        let mut camera = Camera::new([0.0, 0.0, 5.0], [0.0, 0.0, 0.0], glam::Vec3::Y, 1.0, 0.5);
+        let size = |camera: &Camera, z: f32| {
+            let view_proj = camera.build_view_projection_matrix();
+            let left = view_proj.project_point3(glam::vec3(-0.5, -0.5, z));
+            let right = view_proj.project_point3(glam::vec3(0.5, 0.5, z));
+            right.truncate() - left.truncate()
+        };
+        let (near, far) = (size(&camera, 2.0), size(&camera, -2.0));
+        assert!(
+            near.x > far.x,
+            "Perspective square is {near} near and {far} far"
+        );
+        camera.toggle_projection();
+        let (near, far) = (size(&camera, 2.0), size(&camera, -2.0));
+        assert!(
+            near.abs_diff_eq(far, 1e-5),
+            "Orthographic square is {near} near and {far} far"
+        );
+    }
+
+    /// Seen through a projector, its target is in the middle of the image and the eye is where
+    /// the projector is
+    #[test]
+    fn camera_uniform_from_projector() {
+        let projector = Projector {
+            target: glam::vec3(1.0, 0.0, -1.0),
+            ..Projector::from_camera(&camera())
+        };
+        let mut uniform = CameraUniform::new();
+        uniform.update_from_projector(&projector);
+        let view_proj = glam::Mat4::from_cols_array_2d(&uniform.view_proj);
+        let ndc = view_proj.project_point3(projector.target);
+        assert!(
+            ndc.truncate().abs_diff_eq(glam::Vec2::ZERO, 1e-5),
+            "Target at {ndc}"
+        );
+        assert_eq!(
+            uniform.view_pos,
+            projector.eye.extend(1.0).to_array(),
+            "Eye position"
+        );
+    }
+
+    /// A projector built from a look-at reports the eye it was given and faces the target
+    #[test]
+    fn projector_from_look_at() {
+        let (eye, target) = (glam::vec3(2.0, 3.0, 4.0), glam::vec3(-1.0, 0.5, 0.0));
+        let projector = Projector::from_look_at(eye, target, glam::Vec3::Y);
+        assert_eq!(projector.position(), eye, "Projector position");
+        let forward = projector.forward();
+        let expected = (target - eye).normalize();
+        assert!(
+            forward.abs_diff_eq(expected, 1e-6),
+            "Forward is {forward} instead of {expected}"
+        );
+        let center = projector
+            .project_point(target)
+            .expect("Target is in the image");
+        assert!(
+            center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+            "Target lands at {center}"
+        );
+    }
+
+    /// A flipped projector shows the point at the mirrored texture coordinates
+    #[test]
+    fn flipped_projector_mirrors_image() {
+        let projector = Projector::from_camera(&camera());
+        let point = glam::vec3(1.0, 0.0, -0.5);
+        let uv = projector
+            .project_point(point)
+            .expect("Point is in the image");
+        for (horizontal, vertical) in [(true, false), (false, true), (true, true)] {
+            let flipped = projector
+                .with_flip(horizontal, vertical)
+                .project_point(point)
+                .expect("Point is in the flipped image");
+            let expected = glam::vec2(
+                if horizontal { 1.0 - uv.x } else { uv.x },
+                if vertical { 1.0 - uv.y } else { uv.y },
+            );
+            assert!(
+                flipped.abs_diff_eq(expected, 1e-5),
+                "Flipped {horizontal}, {vertical} lands at {flipped} instead of {expected}"
+            );
+        }
+    }
+
+    /// Slerping no way towards a rotation keeps the projector as it is, and all the way turns it
+    /// to the rotation in place
+    #[test]
+    fn slerp_endpoints() {
+        let projector = Projector::from_camera(&camera());
+        let rotation = glam::Quat::from_rotation_y(1.0) * projector.rotation();
+        let start = projector.slerp_to(rotation, 0.0);
+        assert_eq!(
+            (start.eye, start.target, start.up),
+            (projector.eye, projector.target, projector.up),
+            "Pose at t 0"
+        );
+        let end = projector.slerp_to(rotation, 1.0);
+        assert_eq!(end.eye, projector.eye, "Eye at t 1");
+        let turned = end.rotation();
+        assert!(
+            turned.dot(rotation).abs() > 1.0 - 1e-6,
+            "Rotation at t 1 is {turned} instead of {rotation}"
+        );
+        let distance = end.eye.distance(end.target);
+        let expected = projector.eye.distance(projector.target);
+        assert!(
+            (distance - expected).abs() < 1e-4,
+            "Target is {distance} away instead of {expected}"
+        );
+    }
+
+    /// The target and the corners are in the frustum, points behind the projector or past its
+    /// sides aren't, and points on the near and far planes count as inside
+    #[test]
+    fn frustum_contains_points() {
+        let projector = Projector::from_camera(&camera());
+        let forward = projector.forward();
+        assert!(projector.contains_point(projector.target), "Target");
+        assert!(
+            !projector.contains_point(projector.eye - forward),
+            "Point behind the projector"
+        );
+        let side = forward.cross(projector.up).normalize() * 100.0;
+        assert!(
+            !projector.contains_point(projector.target + side),
+            "Point far to the side"
+        );
+        for (plane, distance) in [("near", projector.znear), ("far", projector.zfar)] {
+            let on_plane = projector.eye + forward * distance;
+            assert!(
+                projector.contains_point(on_plane),
+                "Point on the {plane} plane"
+            );
+            let outside = if plane == "near" { -0.01 } else { 0.01 };
+            assert!(
+                !projector.contains_point(on_plane + forward * outside),
+                "Point just past the {plane} plane"
+            );
+        }
+        let corners = projector.frustum_corners();
+        let center = corners.iter().sum::<glam::Vec3>() / 8.0;
+        for corner in corners {
+            let inside = corner.lerp(center, 0.01);
+            assert!(
+                projector.contains_point(inside),
+                "Just inside corner {corner}"
+            );
+        }
+    }
+
+    /// Looking straight down from 2 above the floor with a 90° field of view covers a 4 × 4
+    /// square, and nothing of a plane behind the projector
+    #[test]
+    fn footprint_on_floor() {
+        let projector = Projector::from_camera(&Camera::new(
+            [0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0],
+            glam::Vec3::Z,
+            1.0,
+            std::f32::consts::FRAC_PI_2,
+        ));
+        let footprint = projector.footprint(glam::Vec3::ZERO, glam::Vec3::Y);
+        assert_eq!(footprint.len(), 4, "Footprint {footprint:?}");
+        for corner in &footprint {
+            assert!(
+                corner.abs().abs_diff_eq(glam::vec3(2.0, 0.0, 2.0), 1e-4),
+                "Corner {corner}"
+            );
+        }
+        let turn = (footprint[1] - footprint[0]).cross(footprint[2] - footprint[1]);
+        assert!(turn.y > 0.0, "Counter-clockwise around the normal");
+        let above = projector.footprint(glam::vec3(0.0, 3.0, 0.0), glam::Vec3::Y);
+        assert!(above.is_empty(), "Footprint behind the projector {above:?}");
+    }
+
+    /// A decal is centered on its point and reaches half its size to each side, but not past
+    /// the projector it is stamped from
+    #[test]
+    fn decal_covers_square_on_surface() {
+        let position = glam::vec3(1.0, 2.0, 3.0);
+        let decal = Projector::decal(position, glam::Vec3::X * 2.0, 0.4);
+        let center = decal
+            .project_point(position)
+            .expect("Decal covers its point");
+        assert!(
+            center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+            "Center at {center}"
+        );
+        let side = decal.project_point(position + glam::Vec3::Z * 0.19);
+        assert!(side.is_some(), "Just inside the side");
+        let past = decal.project_point(position + glam::Vec3::Z * 0.21);
+        assert!(past.is_none(), "Just past the side at {past:?}");
+        let behind = decal.project_point(position + glam::Vec3::X);
+        assert!(behind.is_none(), "Behind the decal at {behind:?}");
+        assert!(decal.weight > 1.0, "Decal weight {}", decal.weight);
+    }
+
+    /// Shifting the lens moves the principal point of a projector from intrinsics by the same
+    /// amount as changing `cx`, `cy`, and slides the image without turning the projector
+    #[test]
+    fn lens_shift_offsets_principal_point() {
+        let (width, height) = (1920, 1080);
+        let intrinsics =
+            |cx, cy| Projector::from_intrinsics(1500.0, 1500.0, cx, cy, width, height, 0.1, 100.0);
+        let shift = glam::vec2(0.1, -0.05);
+        let shifted = intrinsics(960.0, 540.0).with_lens_shift(shift);
+        let expected = intrinsics(960.0 + 0.1 * width as f32, 540.0 - 0.05 * height as f32);
+        assert!(
+            shifted
+                .projection_matrix()
+                .abs_diff_eq(expected.projection_matrix(), 1e-6),
+            "Shifted {} instead of {}",
+            shifted.projection_matrix(),
+            expected.projection_matrix()
+        );
+
+        for projector in [
+            Projector::from_camera(&camera()),
+            Projector::from_camera(&camera()).with_ortho_height(4.0),
+        ] {
+            let shifted = projector.with_lens_shift(shift);
+            assert_eq!(
+                shifted.forward(),
+                projector.forward(),
+                "Shifting turned the projector"
+            );
+            let uv = shifted
+                .project_point(projector.target)
+                .expect("Target is outside the shifted image");
+            assert!(
+                uv.abs_diff_eq(glam::vec2(0.5, 0.5) + shift, 1e-5),
+                "Target lands at {uv}"
+            );
+        }
+    }
+
+    /// A fisheye projector lands the axis at the image center, directions at half its field of
+    /// view on the edge of the image circle, even past 90°, and misses those further out
+    #[test]
+    fn fisheye_maps_angles_to_radius() {
+        for model in [Fisheye::Equidistant, Fisheye::Equisolid] {
+            let projector = Projector::fisheye(200.0, model);
+            let center = projector.project_point(glam::Vec3::NEG_Z).expect("Axis");
+            assert!(
+                center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-6),
+                "{model:?} axis lands at {center}"
+            );
+            // 100° off the axis, to the right and up
+            let angle = 100f32.to_radians();
+            let right = glam::vec3(angle.sin(), 0.0, -angle.cos());
+            let edge = projector.project_point(right).expect("Right edge");
+            assert!(
+                edge.abs_diff_eq(glam::vec2(1.0, 0.5), 1e-5),
+                "{model:?} edge lands at {edge}"
+            );
+            let up = glam::vec3(0.0, angle.sin(), -angle.cos());
+            let top = projector.project_point(up).expect("Top edge");
+            assert!(
+                top.abs_diff_eq(glam::vec2(0.5, 0.0), 1e-5),
+                "{model:?} top lands at {top}"
+            );
+            assert!(
+                projector.project_point(glam::Vec3::Z).is_none(),
+                "{model:?} projects straight behind"
+            );
+        }
+        // Equidistant: half the angle, half the radius
+        let projector = Projector::fisheye(180.0, Fisheye::Equidistant);
+        let angle = 45f32.to_radians();
+        let uv = projector
+            .project_point(glam::vec3(angle.sin(), 0.0, -angle.cos()))
+            .expect("Inside");
+        assert!(
+            uv.abs_diff_eq(glam::vec2(0.75, 0.5), 1e-5),
+            "45° lands at {uv}"
+        );
+    }
+
+    /// Points inside a concave mask polygon are projected, those in its notch or outside aren't
+    #[test]
+    fn mask_polygon_clips_projection() {
+        // A U shape open at the top of the image
+        let mask = [
+            (0.1, 0.1),
+            (0.4, 0.1),
+            (0.4, 0.6),
+            (0.6, 0.6),
+            (0.6, 0.1),
+            (0.9, 0.1),
+            (0.9, 0.9),
+            (0.1, 0.9),
+        ]
+        .map(glam::Vec2::from);
+        let polygon = MaskPolygon::new(&mask);
+        for (uv, inside) in [
+            ((0.2, 0.2), true),
+            ((0.5, 0.8), true),
+            ((0.5, 0.3), false),
+            ((0.05, 0.5), false),
+            ((0.5, 0.95), false),
+        ] {
+            let uv = glam::Vec2::from(uv);
+            assert_eq!(polygon.contains(uv), inside, "{uv} inside");
+        }
+
+        let projector = Projector::from_camera(&camera()).with_mask_polygon(&mask);
+        assert!(
+            projector.project_point(projector.target).is_none(),
+            "Target in the notch is projected"
+        );
+        let uniform = ProjectorUniform::from(&projector);
+        assert_eq!(uniform.mask_len, 8, "Uploaded vertex count");
+        assert_eq!(
+            uniform.mask[1],
+            [0.4, 0.6, 0.6, 0.6],
+            "Uploaded vertex pair"
+        );
+
+        let too_many = [glam::Vec2::ZERO; MaskPolygon::MAX_VERTICES + 1];
+        let capped = MaskPolygon::new(&too_many);
+        assert_eq!(
+            capped.vertices().len(),
+            MaskPolygon::MAX_VERTICES,
+            "Capped vertices"
+        );
+    }
+
+    /// An animation ends at its rotation and eye, and stays there
+    #[test]
+    fn projector_animation_ends_at_target() {
+        let projector = Projector::from_camera(&camera());
+        let rotation = glam::Quat::from_rotation_x(-0.3) * projector.rotation();
+        let eye = glam::vec3(0.0, 8.0, 8.0);
+        let mut animation = ProjectorAnimation::new(projector, rotation, eye, 2.0);
+        animation.advance(1.0);
+        assert!(!animation.is_finished(), "Finished halfway");
+        let end = animation.advance(1.5);
+        assert!(animation.is_finished(), "Not finished past the duration");
+        assert!(end.eye.abs_diff_eq(eye, 1e-5), "Ends at {}", end.eye);
+        let turned = end.rotation();
+        assert!(
+            turned.dot(rotation).abs() > 1.0 - 1e-6,
+            "Ends turned to {turned} instead of {rotation}"
+        );
     }
 }
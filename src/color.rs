@@ -0,0 +1,41 @@
+//! Conversions between sRGB encoded colors, as picked in an image editor, and the linear values
+//! the renderer works in.  Render targets with an sRGB format encode linear output on write, so
+//! colors handed to wgpu (clear colors, shader inputs) have to be linear.
+
+/// Decode one sRGB encoded channel in `[0, 1]` to linear
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode one linear channel in `[0, 1]` as sRGB
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Decode the color channels of an sRGB color.  Alpha is always linear and kept as is.
+pub fn srgb_color_to_linear(color: wgpu::Color) -> wgpu::Color {
+    wgpu::Color {
+        r: srgb_to_linear(color.r),
+        g: srgb_to_linear(color.g),
+        b: srgb_to_linear(color.b),
+        a: color.a,
+    }
+}
+
+/// Encode the color channels of a linear color as sRGB, keeping alpha
+pub fn linear_color_to_srgb(color: wgpu::Color) -> wgpu::Color {
+    wgpu::Color {
+        r: linear_to_srgb(color.r),
+        g: linear_to_srgb(color.g),
+        b: linear_to_srgb(color.b),
+        a: color.a,
+    }
+}
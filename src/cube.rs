@@ -1,5 +1,5 @@
-use crate::{model::{ModelVertex, self}, texture};
-use wgpu::util::DeviceExt;
+use crate::camera;
+use crate::model::{self, LineVertex, ModelVertex};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -8,8 +8,8 @@ pub struct SimpleVertex {
     pub tex_coords: [f32; 2],
 }
 
-impl SimpleVertex {
-    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+impl model::Vertex for SimpleVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<SimpleVertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
@@ -29,12 +29,38 @@ impl SimpleVertex {
     }
 }
 
+impl From<SimpleVertex> for ModelVertex {
+    /// The normal defaults to +Z; primitives that know better set it themselves.
+    fn from(value: SimpleVertex) -> Self {
+        Self {
+            position: value.position,
+            tex_coords: value.tex_coords,
+            normal: [0.0, 0.0, 1.0],
+        }
+    }
+}
+
 pub struct Cube(pub model::Mesh);
 
 impl Cube {
-    pub fn new(name: &str, device: &wgpu::Device) -> Self {
+    #[rustfmt::skip]
+    const INDICES: [u32; 36] = [
+        0, 1, 2,
+        2, 3, 0,
+        2, 4, 3,
+        3, 4, 5,
+        4, 6, 5,
+        6, 7, 5,
+        6, 4, 1,
+        4, 2, 1,
+        1, 7, 6,
+        0, 7, 1,
+        0, 3, 7,
+        7, 3, 5,
+    ];
+
+    fn vertices(scale: f32) -> [SimpleVertex; 8] {
         #[rustfmt::skip]
-        let scale = 2.0;
         let vertices = [
             SimpleVertex { position: [-0.5 * scale,  0.5 * scale,  0.5 * scale], tex_coords: [-1.0, -1.0] },
             SimpleVertex { position: [-0.5 * scale, -0.5 * scale,  0.5 * scale], tex_coords: [-1.0, -1.0] },
@@ -46,44 +72,109 @@ impl Cube {
             SimpleVertex { position: [-0.5 * scale, -0.5 * scale, -0.5 * scale], tex_coords: [-1.0, -1.0] },
             SimpleVertex { position: [-0.5 * scale,  0.5 * scale, -0.5 * scale], tex_coords: [-1.0, -1.0] },
         ];
+        vertices
+    }
 
+    /// Cube with sides of length `scale`, centered on the origin.  Every face has its own four
+    /// vertices with the face's normal and the whole texture mapped onto it, upright where the
+    /// face has an up direction in [`Cube::new_textured`].
+    pub fn new(name: &str, scale: f32, device: &wgpu::Device) -> Self {
+        let (vertices, indices) = Self::geometry(scale);
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
+        Self(mesh)
+    }
 
-        #[rustfmt::skip]
-        let indices = [
-            0, 1, 2,
-            2, 3, 0,
-            2, 4, 3,
-            3, 4, 5,
-            4, 6, 5,
-            6, 7, 5,
-            6, 4, 1,
-            4, 2, 1,
-            1, 7, 6,
-            0, 7, 1,
-            0, 3, 7,
-            7, 3, 5,
-        ];
+    /// Vertices and indices of [`Cube::new`]
+    fn geometry(scale: f32) -> (Vec<ModelVertex>, Vec<u32>) {
+        let full = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+        let vertices = Self::faces(0.5 * scale, [full; 6]).concat();
+        let indices = (0..6)
+            .flat_map(|face| [0, 1, 2, 2, 3, 0].map(|i| 4 * face + i))
+            .collect();
+        (vertices, indices)
+    }
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Vertex Buffer", name)),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+    /// Cube with sides of length 2 and smooth normals pointing away from its center, sharing
+    /// its eight corners between the faces.  Only suited to projective mapping, since it has no
+    /// meaningful UVs.
+    pub fn new_with_normals(name: &str, device: &wgpu::Device) -> Self {
+        let vertices = Self::vertices(2.0).map(|v| ModelVertex {
+            normal: glam::Vec3::from(v.position).normalize().into(),
+            ..v.into()
         });
+        Self::from_vertices(name, &vertices, device)
+    }
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Index Buffer", name)),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+    /// A cube split into one mesh per face, each with its own material and UVs, e.g. a labeled
+    /// calibration cube.  Faces are ordered +X, -X, +Y, -Y, +Z, -Z and their UVs run
+    /// bottom-left, bottom-right, top-right, top-left as seen from outside the cube.
+    pub fn new_textured(
+        name: &str,
+        device: &wgpu::Device,
+        materials: [usize; 6],
+        face_uvs: [[[f32; 2]; 4]; 6],
+    ) -> Vec<model::Mesh> {
+        Self::faces(1.0, face_uvs)
+            .iter()
+            .zip(materials)
+            .enumerate()
+            .map(|(i, (vertices, material))| {
+                model::Mesh::from_vertices(
+                    &format!("{name} face {i}"),
+                    device,
+                    vertices,
+                    &[0, 1, 2, 2, 3, 0],
+                    material,
+                )
+            })
+            .collect()
+    }
+
+    /// Corners of the faces of a cube reaching `half` from the origin, in the order and with the
+    /// UV layout of [`Cube::new_textured`], each with the face's normal
+    fn faces(half: f32, face_uvs: [[[f32; 2]; 4]; 6]) -> [[ModelVertex; 4]; 6] {
+        #[rustfmt::skip]
+        let faces = [
+            (glam::Vec3::X,     glam::Vec3::Y),
+            (glam::Vec3::NEG_X, glam::Vec3::Y),
+            (glam::Vec3::Y,     glam::Vec3::NEG_Z),
+            (glam::Vec3::NEG_Y, glam::Vec3::Z),
+            (glam::Vec3::Z,     glam::Vec3::Y),
+            (glam::Vec3::NEG_Z, glam::Vec3::Y),
+        ];
+        std::array::from_fn(|i| {
+            let (normal, up) = faces[i];
+            // `right x up == normal` keeps the corners counter-clockwise from outside
+            let right = up.cross(normal);
+            let corners = [-right - up, right - up, right + up, -right + up];
+            std::array::from_fn(|j| ModelVertex {
+                position: ((corners[j] + normal) * half).into(),
+                tex_coords: face_uvs[i][j],
+                normal: normal.into(),
+            })
+        })
+    }
 
-        let mesh = model::Mesh {
-            name: format!("{:?} mesh", name),
-            vertex_buffer,
-            index_buffer,
-            num_elements: indices.len() as u32,
-            material: 0,
+    /// UVs for [`Cube::new_textured`] reading the faces from a horizontal cross: -X, +Z, +X, -Z
+    /// left to right across the middle row with +Y above and -Y below +Z.
+    pub fn cross_layout_uvs() -> [[[f32; 2]; 4]; 6] {
+        let cell = |column: f32, row: f32| {
+            let (u0, u1) = (column / 4.0, (column + 1.0) / 4.0);
+            let (v0, v1) = (row / 3.0, (row + 1.0) / 3.0);
+            [[u0, v1], [u1, v1], [u1, v0], [u0, v0]]
         };
+        [
+            cell(2.0, 1.0),
+            cell(0.0, 1.0),
+            cell(1.0, 0.0),
+            cell(1.0, 2.0),
+            cell(1.0, 1.0),
+            cell(3.0, 1.0),
+        ]
+    }
 
+    fn from_vertices(name: &str, vertices: &[ModelVertex], device: &wgpu::Device) -> Self {
+        let mesh = model::Mesh::from_vertices(name, device, vertices, &Self::INDICES, 0);
         Self(mesh)
     }
 }
@@ -104,7 +195,8 @@ impl Plane {
             SimpleVertex { position: [-2.0, -1.0,  2.0], tex_coords: [-1.0, -1.0] },
             SimpleVertex { position: [ 2.0, -1.0,  2.0], tex_coords: [-1.0, -1.0] },
             SimpleVertex { position: [ 2.0, -1.0, -2.0], tex_coords: [-1.0, -1.0] },
-        ];
+        ]
+        .map(|v| ModelVertex { normal: [0.0, 1.0, 0.0], ..v.into() });
 
         #[rustfmt::skip]
         let indices = [
@@ -112,25 +204,7 @@ impl Plane {
             2, 3, 0,
         ];
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Vertex Buffer", name)),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Index Buffer", name)),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        let mesh = model::Mesh {
-            name: format!("{:?} mesh", name),
-            vertex_buffer,
-            index_buffer,
-            num_elements: indices.len() as u32,
-            material: 0,
-        };
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
 
         Self(mesh)
     }
@@ -152,7 +226,8 @@ impl Billboard {
             SimpleVertex { position: [-3.0, -4.0,  4.0], tex_coords: [0.0, 0.0] },
             SimpleVertex { position: [-3.0, -4.0, -4.0], tex_coords: [1.0, 0.0] },
             SimpleVertex { position: [-3.0,  4.0, -4.0], tex_coords: [1.0, 1.0] },
-        ];
+        ]
+        .map(|v| ModelVertex { normal: [1.0, 0.0, 0.0], ..v.into() });
 
         #[rustfmt::skip]
         let indices = [
@@ -160,25 +235,30 @@ impl Billboard {
             2, 3, 0,
         ];
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Vertex Buffer", name)),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("{:?} Index Buffer", name)),
-            contents: bytemuck::cast_slice(&indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        Self(mesh)
+    }
 
-        let mesh = model::Mesh {
-            name: format!("{:?} mesh", name),
-            vertex_buffer,
-            index_buffer,
-            num_elements: indices.len() as u32,
-            material: 0,
-        };
+    /// Unit square around the origin in the XY plane, facing +Z with the image upright, which
+    /// `Instance::facing` turns towards the camera
+    pub fn unit(name: &str, device: &wgpu::Device) -> Self {
+        #[rustfmt::skip]
+        let vertices = [
+            SimpleVertex { position: [-0.5,  0.5, 0.0], tex_coords: [0.0, 0.0] },
+            SimpleVertex { position: [-0.5, -0.5, 0.0], tex_coords: [0.0, 1.0] },
+            SimpleVertex { position: [ 0.5, -0.5, 0.0], tex_coords: [1.0, 1.0] },
+            SimpleVertex { position: [ 0.5,  0.5, 0.0], tex_coords: [1.0, 0.0] },
+        ]
+        .map(|v| ModelVertex { normal: [0.0, 0.0, 1.0], ..v.into() });
+
+        #[rustfmt::skip]
+        let indices = [
+            0, 1, 2,
+            2, 3, 0,
+        ];
+
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
 
         Self(mesh)
     }
@@ -189,3 +269,440 @@ impl From<Billboard> for model::Mesh {
         value.0
     }
 }
+
+pub struct Sphere(pub model::Mesh);
+
+impl Sphere {
+    /// UV sphere around the origin, split into `rings` from pole to pole and `sectors` around
+    /// the Y axis, each at least 3.  `u` runs once around and `v` from the top pole down; the
+    /// seam and pole vertices are duplicated so the texture doesn't wrap back across a triangle.
+    ///
+    /// The rings at the poles are fans of triangles meeting at the pole.  With `poles_as_fans`
+    /// each of them gets a pole vertex in the middle of its sector's `u`, so an equirectangular
+    /// image converges evenly; otherwise it shares `u` with the sector's first edge and is
+    /// sheared towards it.
+    pub fn new(
+        name: &str,
+        radius: f32,
+        rings: u32,
+        sectors: u32,
+        poles_as_fans: bool,
+        device: &wgpu::Device,
+    ) -> Self {
+        let (vertices, indices) = Self::geometry(radius, rings, sectors, poles_as_fans);
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
+        Self(mesh)
+    }
+
+    /// Vertices and indices of [`Sphere::new`]
+    fn geometry(
+        radius: f32,
+        rings: u32,
+        sectors: u32,
+        poles_as_fans: bool,
+    ) -> (Vec<ModelVertex>, Vec<u32>) {
+        let (rings, sectors) = (rings.max(3), sectors.max(3));
+        let mut vertices = Vec::with_capacity(((rings + 1) * (sectors + 1)) as usize);
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let (sin_phi, cos_phi) = (v * std::f32::consts::PI).sin_cos();
+            let pole = ring == 0 || ring == rings;
+            for sector in 0..=sectors {
+                let u = sector as f32 / sectors as f32;
+                let (sin_theta, cos_theta) = (u * std::f32::consts::TAU).sin_cos();
+                let normal = glam::vec3(sin_phi * sin_theta, cos_phi, sin_phi * cos_theta);
+                // The last pole vertex, past the seam, is in no triangle
+                let u = if pole && poles_as_fans && sector < sectors {
+                    (sector as f32 + 0.5) / sectors as f32
+                } else {
+                    u
+                };
+                vertices.push(ModelVertex {
+                    position: (normal * radius).into(),
+                    tex_coords: [u, v],
+                    normal: normal.into(),
+                });
+            }
+        }
+
+        // Counter-clockwise from outside; the triangles that would collapse onto a pole are
+        // left out
+        let mut indices = Vec::with_capacity((6 * rings * sectors) as usize);
+        for ring in 0..rings {
+            for sector in 0..sectors {
+                let top_left = ring * (sectors + 1) + sector;
+                let bottom_left = top_left + sectors + 1;
+                if ring != rings - 1 {
+                    indices.extend([top_left, bottom_left, bottom_left + 1]);
+                }
+                if ring != 0 {
+                    // Each fan triangle at the bottom pole takes the pole vertex on its sector's
+                    // first edge, like at the top
+                    let bottom = if ring == rings - 1 {
+                        bottom_left
+                    } else {
+                        bottom_left + 1
+                    };
+                    indices.extend([top_left, bottom, top_left + 1]);
+                }
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+impl From<Sphere> for model::Mesh {
+    fn from(value: Sphere) -> Self {
+        value.0
+    }
+}
+
+pub struct Cylinder(pub model::Mesh);
+
+impl Cylinder {
+    /// Closed cylinder around the Y axis, centered on the origin, with at least 3 `segments`.
+    /// The side's `u` runs once around and its `v` from top to bottom; each cap maps to the
+    /// disc inscribed in the texture.
+    pub fn new(name: &str, radius: f32, height: f32, segments: u32, device: &wgpu::Device) -> Self {
+        let (vertices, indices) = Self::geometry(radius, height, segments);
+        let mesh = model::Mesh::from_vertices(name, device, &vertices, &indices, 0);
+        Self(mesh)
+    }
+
+    /// Vertices and indices of [`Cylinder::new`]
+    fn geometry(radius: f32, height: f32, segments: u32) -> (Vec<ModelVertex>, Vec<u32>) {
+        let segments = segments.max(3);
+        let half_height = height * 0.5;
+        let around = |segment: u32| {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (u * std::f32::consts::TAU).sin_cos();
+            (u, glam::vec3(sin, 0.0, cos))
+        };
+
+        // Side wall, a top and a bottom vertex per segment edge with the seam duplicated
+        let mut vertices = Vec::new();
+        for segment in 0..=segments {
+            let (u, normal) = around(segment);
+            for (y, v) in [(half_height, 0.0), (-half_height, 1.0)] {
+                vertices.push(ModelVertex {
+                    position: (normal * radius + glam::Vec3::Y * y).into(),
+                    tex_coords: [u, v],
+                    normal: normal.into(),
+                });
+            }
+        }
+        let mut indices = Vec::new();
+        for segment in 0..segments {
+            let top_left = 2 * segment;
+            let (bottom_left, top_right, bottom_right) = (top_left + 1, top_left + 2, top_left + 3);
+            indices.extend([top_left, bottom_left, bottom_right]);
+            indices.extend([top_left, bottom_right, top_right]);
+        }
+
+        // Caps, a fan around a center vertex each, counter-clockwise seen from outside
+        for normal in [glam::Vec3::Y, glam::Vec3::NEG_Y] {
+            let center = vertices.len() as u32;
+            let y = normal.y * half_height;
+            vertices.push(ModelVertex {
+                position: [0.0, y, 0.0],
+                tex_coords: [0.5, 0.5],
+                normal: normal.into(),
+            });
+            for segment in 0..segments {
+                let (_, direction) = around(segment);
+                vertices.push(ModelVertex {
+                    position: (direction * radius + glam::Vec3::Y * y).into(),
+                    tex_coords: [0.5 + 0.5 * direction.x, 0.5 + 0.5 * direction.z],
+                    normal: normal.into(),
+                });
+            }
+            for segment in 0..segments {
+                let current = center + 1 + segment;
+                let next = center + 1 + (segment + 1) % segments;
+                if normal.y > 0.0 {
+                    indices.extend([center, current, next]);
+                } else {
+                    indices.extend([center, next, current]);
+                }
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+impl From<Cylinder> for model::Mesh {
+    fn from(value: Cylinder) -> Self {
+        value.0
+    }
+}
+
+/// The twelve edges of a frustum, e.g. from [`crate::camera::Projector::frustum_corners`], as a
+/// line list
+pub struct FrustumLines(pub model::LineMesh);
+
+impl FrustumLines {
+    #[rustfmt::skip]
+    const INDICES: [u32; 24] = [
+        // Near plane
+        0, 1, 1, 2, 2, 3, 3, 0,
+        // Far plane
+        4, 5, 5, 6, 6, 7, 7, 4,
+        // Sides
+        0, 4, 1, 5, 2, 6, 3, 7,
+    ];
+
+    /// Corners in the order of `Projector::frustum_corners`
+    pub fn new(name: &str, corners: [glam::Vec3; 8], device: &wgpu::Device) -> Self {
+        let vertices = corners.map(|corner| LineVertex {
+            position: corner.into(),
+            color: [1.0, 0.6, 0.0],
+        });
+        let mesh = model::LineMesh::new(name, device, &vertices, &Self::INDICES);
+        Self(mesh)
+    }
+}
+
+impl From<FrustumLines> for model::LineMesh {
+    fn from(value: FrustumLines) -> Self {
+        value.0
+    }
+}
+
+/// Ground grid of lines on the XZ plane through the origin, `Y` being up like everywhere else, as
+/// a line list like [`FrustumLines`]
+pub struct Grid(pub model::LineMesh);
+
+impl Grid {
+    /// `divisions` cells across each side of a square reaching `half_extent` from the origin,
+    /// with the lines through the origin colored like the axes they run along, red for `X` and
+    /// blue for `Z`, when `divisions` is even so there are such lines
+    pub fn new_with_axes(
+        name: &str,
+        half_extent: f32,
+        divisions: u32,
+        device: &wgpu::Device,
+    ) -> Self {
+        let (vertices, indices) = Self::geometry(half_extent, divisions);
+        let mesh = model::LineMesh::new(name, device, &vertices, &indices);
+        Self(mesh)
+    }
+
+    /// Vertices and line list indices of [`Grid::new_with_axes`]
+    fn geometry(half_extent: f32, divisions: u32) -> (Vec<LineVertex>, Vec<u32>) {
+        let divisions = divisions.max(1);
+        let line_color = [0.3, 0.3, 0.3];
+        let mut vertices = Vec::with_capacity(4 * (divisions as usize + 1));
+        for i in 0..=divisions {
+            let offset = half_extent * (2.0 * i as f32 / divisions as f32 - 1.0);
+            let center = 2 * i == divisions;
+            // Along X at `z = offset`, then along Z at `x = offset`
+            let color = if center { [1.0, 0.0, 0.0] } else { line_color };
+            let along_x = [[-half_extent, 0.0, offset], [half_extent, 0.0, offset]];
+            vertices.extend(along_x.map(|position| LineVertex { position, color }));
+            let color = if center { [0.0, 0.0, 1.0] } else { line_color };
+            let along_z = [[offset, 0.0, -half_extent], [offset, 0.0, half_extent]];
+            vertices.extend(along_z.map(|position| LineVertex { position, color }));
+        }
+        let indices = (0..vertices.len() as u32).collect();
+        (vertices, indices)
+    }
+}
+
+impl From<Grid> for model::LineMesh {
+    fn from(value: Grid) -> Self {
+        value.0
+    }
+}
+
+/// Layout of projectors over a floor as a line list like [`FrustumLines`]: each projector's
+/// [`camera::Projector::footprint`] on the floor, a diamond around its eye and a line towards
+/// its target, see `State::export_schematic`
+pub struct Schematic(pub model::LineMesh);
+
+impl Schematic {
+    /// `projectors` over the floor through `floor` facing `up`, with diamonds reaching
+    /// `icon_size` from their eyes
+    pub fn new(
+        name: &str,
+        projectors: &[camera::Projector],
+        floor: glam::Vec3,
+        up: glam::Vec3,
+        icon_size: f32,
+        device: &wgpu::Device,
+    ) -> Self {
+        let (vertices, indices) = Self::geometry(projectors, floor, up, icon_size);
+        let mesh = model::LineMesh::new(name, device, &vertices, &indices);
+        Self(mesh)
+    }
+
+    /// Vertices and line list indices of [`Schematic::new`]
+    fn geometry(
+        projectors: &[camera::Projector],
+        floor: glam::Vec3,
+        up: glam::Vec3,
+        icon_size: f32,
+    ) -> (Vec<LineVertex>, Vec<u32>) {
+        let up = up.normalize();
+        let x = up.any_orthonormal_vector() * icon_size;
+        let y = up.cross(x);
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut add_loop = |points: &[glam::Vec3], color: [f32; 3]| {
+            let start = vertices.len() as u32;
+            let len = points.len() as u32;
+            vertices.extend(points.iter().map(|&p| LineVertex {
+                position: p.into(),
+                color,
+            }));
+            indices.extend((0..len).flat_map(|i| [start + i, start + (i + 1) % len]));
+        };
+        for projector in projectors {
+            add_loop(&projector.footprint(floor, up), [1.0, 0.6, 0.0]);
+            let eye = projector.eye;
+            add_loop(&[eye + x, eye + y, eye - x, eye - y], [1.0, 1.0, 0.0]);
+            // A loop of two points is the line there and back
+            add_loop(&[eye, projector.target], [1.0, 1.0, 0.0]);
+        }
+        (vertices, indices)
+    }
+}
+
+impl From<Schematic> for model::LineMesh {
+    fn from(value: Schematic) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Cube` vertices span ±0.5 * scale, so a scale of 2 is bounded by ±1
+    #[test]
+    fn cube_bounds() {
+        let aabb =
+            model::Aabb::from_points(Cube::vertices(2.0).map(|v| glam::Vec3::from(v.position)));
+        assert_eq!(aabb.min, glam::Vec3::NEG_ONE, "Cube bounds {aabb:?}");
+        assert_eq!(aabb.max, glam::Vec3::ONE, "Cube bounds {aabb:?}");
+    }
+
+    /// Every face has its own four vertices and two triangles
+    #[test]
+    fn cube_counts() {
+        let (vertices, indices) = Cube::geometry(2.0);
+        assert_eq!(vertices.len(), 24, "Cube vertices");
+        assert_eq!(indices.len(), 36, "Cube indices");
+    }
+
+    /// Two lines per grid line across each direction
+    #[test]
+    fn grid_counts() {
+        let (vertices, indices) = Grid::geometry(2.0, 4);
+        assert_eq!(vertices.len(), 20, "Grid with 4 divisions has vertices");
+        assert_eq!(indices.len(), 20, "Grid with 4 divisions has indices");
+    }
+
+    /// A projector looking down at the floor adds its 4 sided footprint, a diamond and its aim,
+    /// each as a closed loop
+    #[test]
+    fn schematic_counts() {
+        let projector = camera::Projector::from_camera(&camera::Camera::new(
+            [0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0],
+            glam::Vec3::Z,
+            1.0,
+            1.0,
+        ));
+        let (vertices, indices) =
+            Schematic::geometry(&[projector], glam::Vec3::ZERO, glam::Vec3::Y, 0.1);
+        assert_eq!(vertices.len(), 4 + 4 + 2, "Schematic vertices");
+        assert_eq!(indices.len(), 2 * (4 + 4 + 2), "Schematic indices");
+    }
+
+    /// A vertex per ring and sector boundary, seam and poles included, all with unit normals
+    #[test]
+    fn sphere_vertices() {
+        let (rings, sectors) = (8, 12);
+        let (vertices, _) = Sphere::geometry(0.5, rings, sectors, false);
+        assert_eq!(
+            vertices.len() as u32,
+            (rings + 1) * (sectors + 1),
+            "Sphere with {rings} rings and {sectors} sectors has vertices"
+        );
+        for (i, vertex) in vertices.iter().enumerate() {
+            let length = glam::Vec3::from(vertex.normal).length();
+            assert!(
+                (length - 1.0).abs() < 1e-5,
+                "Sphere vertex {i} has a normal of length {length}"
+            );
+        }
+    }
+
+    /// The seam is a column of vertices with `u` 0 and one with `u` 1 at the same positions, and
+    /// no triangle reaches across it, so an equirectangular image isn't smeared back around the
+    /// sphere.  Fanned poles sit in the middle of their sectors.
+    #[test]
+    fn sphere_seam() {
+        let (rings, sectors) = (8, 12);
+        for poles_as_fans in [false, true] {
+            let (vertices, indices) = Sphere::geometry(0.5, rings, sectors, poles_as_fans);
+            for ring in 1..rings {
+                let first = &vertices[(ring * (sectors + 1)) as usize];
+                let last = &vertices[(ring * (sectors + 1) + sectors) as usize];
+                assert_eq!(
+                    (first.tex_coords[0], last.tex_coords[0]),
+                    (0.0, 1.0),
+                    "Seam u on ring {ring}"
+                );
+                let (first, last) = (glam::Vec3::from(first.position), last.position.into());
+                assert!(
+                    first.abs_diff_eq(last, 1e-5),
+                    "Seam vertices on ring {ring} are at {first} and {last}"
+                );
+            }
+            for triangle in indices.chunks_exact(3) {
+                let (min, max) = triangle
+                    .iter()
+                    .map(|&i| vertices[i as usize].tex_coords[0])
+                    .fold((f32::MAX, f32::MIN), |(min, max), u| {
+                        (min.min(u), max.max(u))
+                    });
+                let span = max - min;
+                assert!(
+                    span <= 1.0 / sectors as f32 + 1e-5,
+                    "Triangle {triangle:?} spans {span} in u, fanned poles {poles_as_fans}"
+                );
+            }
+            if poles_as_fans {
+                let bottom = rings * (sectors + 1);
+                for sector in 0..sectors {
+                    let expected = (sector as f32 + 0.5) / sectors as f32;
+                    for pole in [sector, bottom + sector] {
+                        let u = vertices[pole as usize].tex_coords[0];
+                        assert!(
+                            (u - expected).abs() < 1e-5,
+                            "Pole vertex {pole} has u {u} instead of {expected}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Two side triangles and a triangle of each cap per segment, none indexing past the vertices
+    #[test]
+    fn cylinder_indices() {
+        let segments = 12;
+        let (vertices, indices) = Cylinder::geometry(0.5, 1.0, segments);
+        assert_eq!(
+            indices.len() as u32,
+            12 * segments,
+            "Cylinder with {segments} segments has indices"
+        );
+        assert!(
+            indices.iter().all(|&i| (i as usize) < vertices.len()),
+            "Cylinder indexes past its {} vertices",
+            vertices.len()
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{bail, Context};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+
+/// GPU format of the pixel data in a DDS file, if it is one we can upload
+pub fn texture_format(dds: &Dds) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as F;
+    if let Some(format) = dds.get_dxgi_format() {
+        return Some(match format {
+            DxgiFormat::R8G8B8A8_UNorm => F::Rgba8Unorm,
+            DxgiFormat::R8G8B8A8_UNorm_sRGB => F::Rgba8UnormSrgb,
+            DxgiFormat::B8G8R8A8_UNorm => F::Bgra8Unorm,
+            DxgiFormat::B8G8R8A8_UNorm_sRGB => F::Bgra8UnormSrgb,
+            DxgiFormat::BC1_UNorm => F::Bc1RgbaUnorm,
+            DxgiFormat::BC1_UNorm_sRGB => F::Bc1RgbaUnormSrgb,
+            DxgiFormat::BC2_UNorm => F::Bc2RgbaUnorm,
+            DxgiFormat::BC2_UNorm_sRGB => F::Bc2RgbaUnormSrgb,
+            DxgiFormat::BC3_UNorm => F::Bc3RgbaUnorm,
+            DxgiFormat::BC3_UNorm_sRGB => F::Bc3RgbaUnormSrgb,
+            DxgiFormat::BC4_UNorm => F::Bc4RUnorm,
+            DxgiFormat::BC5_UNorm => F::Bc5RgUnorm,
+            DxgiFormat::BC7_UNorm => F::Bc7RgbaUnorm,
+            DxgiFormat::BC7_UNorm_sRGB => F::Bc7RgbaUnormSrgb,
+            _ => return None,
+        });
+    }
+    // Legacy headers don't say whether the data is sRGB; treat it like any other image
+    Some(match dds.get_d3d_format()? {
+        D3DFormat::A8B8G8R8 => F::Rgba8UnormSrgb,
+        D3DFormat::A8R8G8B8 => F::Bgra8UnormSrgb,
+        D3DFormat::DXT1 => F::Bc1RgbaUnormSrgb,
+        D3DFormat::DXT3 => F::Bc2RgbaUnormSrgb,
+        D3DFormat::DXT5 => F::Bc3RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+/// Split the pixel data of one array layer into its mip levels
+pub fn mip_levels(
+    format: wgpu::TextureFormat,
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    mut data: &[u8],
+) -> anyhow::Result<Vec<&[u8]>> {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format
+        .block_size(None)
+        .context("Format has no block size")?;
+    let mut levels = Vec::new();
+    for level in 0..mip_level_count {
+        let mip = size.mip_level_size(level, wgpu::TextureDimension::D2);
+        let blocks_wide = mip.width.div_ceil(block_width);
+        let blocks_high = mip.height.div_ceil(block_height);
+        let len = (blocks_wide * blocks_high * block_size) as usize;
+        if data.len() < len {
+            bail!("DDS data ends in mip level {level}");
+        }
+        let (level_data, rest) = data.split_at(len);
+        levels.push(level_data);
+        data = rest;
+    }
+    Ok(levels)
+}
+
+/// Decode a BC1-3 compressed image to RGBA8, for adapters without BC texture support.  Returns
+/// `None` for formats that can't be decoded on the CPU.
+pub fn decompress(
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Option<Vec<u8>> {
+    use wgpu::TextureFormat as F;
+    let block_size = match format.remove_srgb_suffix() {
+        F::Bc1RgbaUnorm => 8,
+        F::Bc2RgbaUnorm | F::Bc3RgbaUnorm => 16,
+        _ => return None,
+    };
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = width.div_ceil(4);
+    let mut rgba = vec![0; width * height * 4];
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        let mut pixels = [[0; 4]; 16];
+        match format.remove_srgb_suffix() {
+            F::Bc1RgbaUnorm => decode_color(block, &mut pixels, true),
+            F::Bc2RgbaUnorm => {
+                decode_color(&block[8..], &mut pixels, false);
+                let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+                for (p, pixel) in pixels.iter_mut().enumerate() {
+                    pixel[3] = ((alpha >> (4 * p)) & 0xf) as u8 * 17;
+                }
+            }
+            _ => {
+                decode_color(&block[8..], &mut pixels, false);
+                decode_alpha(&block[..8], &mut pixels);
+            }
+        }
+        // Copy the block into the image, dropping pixels past the edge
+        let (bx, by) = (i % blocks_wide * 4, i / blocks_wide * 4);
+        for (p, pixel) in pixels.iter().enumerate() {
+            let (x, y) = (bx + p % 4, by + p / 4);
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                rgba[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+    Some(rgba)
+}
+
+/// Decode a BC1 color block.  `punch_through` enables the 1-bit alpha mode used by BC1 itself.
+fn decode_color(block: &[u8], pixels: &mut [[u8; 4]; 16], punch_through: bool) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let expand = |c: u16| {
+        let r = ((c >> 11) & 0x1f) as u32;
+        let g = ((c >> 5) & 0x3f) as u32;
+        let b = (c & 0x1f) as u32;
+        [
+            (r << 3) | (r >> 2),
+            (g << 2) | (g >> 4),
+            (b << 3) | (b >> 2),
+        ]
+    };
+    let (e0, e1) = (expand(c0), expand(c1));
+    let mix = |a: u32, b: u32, wa: u32, wb: u32| ((a * wa + b * wb) / (wa + wb)) as u8;
+    let mut palette = [[0; 4]; 4];
+    for c in 0..3 {
+        palette[0][c] = e0[c] as u8;
+        palette[1][c] = e1[c] as u8;
+        if c0 > c1 || !punch_through {
+            palette[2][c] = mix(e0[c], e1[c], 2, 1);
+            palette[3][c] = mix(e0[c], e1[c], 1, 2);
+        } else {
+            palette[2][c] = mix(e0[c], e1[c], 1, 1);
+        }
+    }
+    palette[0][3] = 255;
+    palette[1][3] = 255;
+    palette[2][3] = 255;
+    palette[3][3] = if c0 > c1 || !punch_through { 255 } else { 0 };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (p, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (2 * p)) & 0x3) as usize];
+    }
+}
+
+/// Decode an interpolated BC3 alpha block
+fn decode_alpha(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    for (p, pixel) in pixels.iter_mut().enumerate() {
+        pixel[3] = palette[((indices >> (3 * p)) & 0x7) as usize];
+    }
+}
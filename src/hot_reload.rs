@@ -0,0 +1,37 @@
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use winit::event_loop::EventLoopProxy;
+
+use crate::UserEvent;
+
+/// Watch the projected image and send [`UserEvent::ImageChanged`] whenever it is written.  The
+/// watch stops when the returned watcher is dropped.
+///
+/// The directory is watched rather than the file: editors that save by writing a temporary file
+/// and renaming it over the image replace the file being watched, which ends its watch.
+pub fn watch_image(
+    path: &Path,
+    proxy: EventLoopProxy<UserEvent>,
+) -> notify::Result<notify::RecommendedWatcher> {
+    let image = PathBuf::from(path);
+    let file_name = image.file_name().map(ToOwned::to_owned);
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        match res {
+            Ok(event) if event.kind.is_modify() || event.kind.is_create() => {
+                let is_image = |path: &PathBuf| path.file_name() == file_name.as_deref();
+                if event.paths.iter().any(is_image) {
+                    // The event loop is gone once the proxy fails, nothing left to notify
+                    let _ = proxy.send_event(UserEvent::ImageChanged(image.clone()));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Image watch error: {e}"),
+        }
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
@@ -1,30 +1,94 @@
+mod args;
 mod camera;
+pub mod color;
 mod cube;
+mod dds;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
+mod light;
 mod model;
 mod resources;
+mod scene;
+#[cfg(not(target_arch = "wasm32"))]
+mod self_test;
+mod stats;
 mod texture;
+mod timeline;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+pub use args::SceneOptions;
+pub use camera::{Camera, Fisheye, MaskPolygon, Projector};
+pub use light::Light;
+pub use model::{Material, Mesh};
+pub use scene::{MeshId, Scene};
+#[cfg(not(target_arch = "wasm32"))]
+pub use self_test::run_self_test;
 
-use log::{info, warn};
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Context;
+use log::{error, info, warn};
 use model::DrawModel;
 use model::Vertex;
+pub use stats::RenderStats;
+pub use timeline::Timeline;
 use wgpu::util::DeviceExt;
 use winit::{
-    dpi::{LogicalSize, PhysicalSize},
+    dpi::{LogicalPosition, PhysicalPosition, PhysicalSize},
     event::*,
-    event_loop::{ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+    monitor::MonitorHandle,
     window::Window,
-    window::WindowBuilder,
+    window::{Fullscreen, WindowBuilder},
 };
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+/// Format of the picking target, which holds the world position and mesh id of a fragment, see
+/// [`Renderer::pick_gpu`]
+#[cfg(not(target_arch = "wasm32"))]
+const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+/// Size of the mesh id uniform of `fs_pick`, a `vec4<u32>`
+#[cfg(not(target_arch = "wasm32"))]
+const PICK_ID_SIZE: u64 = std::mem::size_of::<[u32; 4]>() as u64;
+
+/// Width and height of the images [`State::export_schematic`] writes
+#[cfg(not(target_arch = "wasm32"))]
+const SCHEMATIC_SIZE: u32 = 1024;
+
+/// Formats of the G-buffer targets after the shaded color: world normal, projector texture
+/// coordinates and distance from the camera, see [`Renderer::render_gbuffer`].  Together with
+/// an 8 bit color they fill the 32 bytes per pixel every adapter can render in one pass.
+#[cfg(not(target_arch = "wasm32"))]
+const GBUFFER_FORMATS: [wgpu::TextureFormat; 3] = [
+    wgpu::TextureFormat::Rgba32Float,
+    wgpu::TextureFormat::Rg32Float,
+    wgpu::TextureFormat::R32Float,
+];
+
+/// Most projectors [`State::add_projector`] can blend, the length of the array in `fs_blend`
+const MAX_PROJECTORS: usize = 8;
+
 const DEFAULT_WINDOW_SIZE: PhysicalSize<u32> = PhysicalSize {
     width: 1080,
     height: 1080,
 };
 
+/// Events sent to the event loop from outside of winit
+#[derive(Debug)]
+pub enum UserEvent {
+    /// The projected image file was modified on disk
+    ImageChanged(std::path::PathBuf),
+    /// An image file picked in the browser was read, to be projected
+    ImageLoaded { name: String, bytes: Vec<u8> },
+    /// An image file dropped onto the canvas was read, to be projected alongside the others
+    ImageDropped { name: String, bytes: Vec<u8> },
+    /// A file is dragged over the canvas, or no longer is
+    FileHovered(bool),
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct InstanceRaw {
@@ -63,14 +127,50 @@ impl InstanceRaw {
     }
 }
 
-struct Instance {
-    position: glam::Vec3,
-    rotation: glam::Quat,
+/// Placement of one copy of every mesh, see [`State::set_instances`]
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub position: glam::Vec3,
+    pub rotation: glam::Quat,
+    /// Along the mesh's own axes, negative to mirror it, e.g. for the other half of a
+    /// symmetric setup
+    pub scale: glam::Vec3,
 }
 
 impl Instance {
+    /// The meshes as they are
+    pub const IDENTITY: Self = Self {
+        position: glam::Vec3::ZERO,
+        rotation: glam::Quat::IDENTITY,
+        scale: glam::Vec3::ONE,
+    };
+
+    /// From the mesh's space to the world
+    pub fn model_matrix(&self) -> glam::Mat4 {
+        glam::Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.position)
+    }
+
+    /// Whether the model matrix has a negative determinant, turning the mesh inside out: its
+    /// triangles wind the other way round and are drawn with pipelines culling the other face
+    pub fn is_mirrored(&self) -> bool {
+        self.model_matrix().determinant() < 0.0
+    }
+
+    /// `size` × `size` copy of [`cube::Billboard::unit`] at `position`, turned to face the
+    /// `camera` squarely with the image upright, see [`State::add_billboards`]
+    pub fn facing(camera: &camera::Camera, position: glam::Vec3, size: f32) -> Self {
+        let forward = (camera.target - camera.eye).normalize();
+        let right = forward.cross(camera.up).normalize();
+        let up = right.cross(forward);
+        Self {
+            position,
+            rotation: glam::Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward)),
+            scale: glam::Vec3::splat(size),
+        }
+    }
+
     fn to_raw(&self) -> InstanceRaw {
-        let mat = glam::Mat4::from_rotation_translation(self.rotation, self.position);
+        let mat = self.model_matrix();
         InstanceRaw {
             model: [
                 mat.x_axis.into(),
@@ -82,337 +182,3487 @@ impl Instance {
     }
 }
 
-struct State {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    size: winit::dpi::PhysicalSize<u32>,
-    window: Window,
-    clear_color: wgpu::Color,
-    pipeline: wgpu::RenderPipeline,
-    camera: camera::Camera,
-    camera_controller: camera::CameraController,
-    camera_uniform: camera::CameraUniform,
-    camera_bind_group: wgpu::BindGroup,
-    camera_buffer: wgpu::Buffer,
-    depth_texture: texture::Texture,
-    materials: Vec<model::Material>,
-    meshes: Vec<model::Mesh>,
+/// Element `index` of the Halton sequence in `base`, which fills `[0, 1)` evenly however many
+/// elements are taken, starting at 0
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
 }
 
-impl State {
-    async fn new(window: Window) -> Self {
-        // Ensure neither width nor height is 0
-        let size = match window.inner_size() {
-            PhysicalSize {
-                height: 0,
-                width: 0,
-            } => DEFAULT_WINDOW_SIZE,
-            size => size,
-        };
+/// `n` × `n` copies of the scene's meshes side by side on the XZ plane, centered on the origin
+fn grid_instances(scene: &Scene, n: u32) -> Vec<Instance> {
+    let size = scene
+        .meshes
+        .iter()
+        .map(model::Mesh::aabb)
+        .reduce(|a, b| a.merge(&b))
+        .map_or(glam::Vec3::ONE, |aabb| aabb.extents() * 2.0);
+    let spacing = size.x.max(size.z) * 1.25;
+    let offset = (n as f32 - 1.0) * 0.5;
+    (0..n * n)
+        .map(|i| Instance {
+            position: glam::vec3((i % n) as f32 - offset, 0.0, (i / n) as f32 - offset) * spacing,
+            ..Instance::IDENTITY
+        })
+        .collect()
+}
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
+/// Vertex buffer of `instances` for slot 1 of the render pipelines, which can be rewritten with
+/// as many others
+fn create_instance_buffer(device: &wgpu::Device, instances: &[Instance]) -> wgpu::Buffer {
+    let raw: Vec<InstanceRaw> = instances.iter().map(Instance::to_raw).collect();
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Instance Buffer"),
+        contents: bytemuck::cast_slice(&raw),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    })
+}
 
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+/// Projected image texture and its sampler, bind group 0 of the render pipelines
+fn create_texture_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("texture_bind_group_layout"),
+    })
+}
 
-        let adapter_options = &wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        };
-        let adapter = instance.request_adapter(adapter_options).await.unwrap();
+/// `CameraUniform` buffer, bind group 1 of the render pipelines
+fn create_camera_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some("camera_bind_group_layout"),
+    })
+}
 
-        let descriptor = &wgpu::DeviceDescriptor {
-            features: wgpu::Features::empty(),//wgpu::Features::POLYGON_MODE_LINE,
-            limits: if cfg!(target_arch = "wasm32") {
-                wgpu::Limits::downlevel_webgl2_defaults()
-            } else {
-                wgpu::Limits::default()
+/// `ProjectorUniform` and `LightUniform` buffers, bind group 2 of the render pipelines.  The
+/// light shares the group since the flipbook and projector blending already use the fourth and
+/// last group WebGL allows.
+fn create_projector_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
             },
-            label: None,
-        };
-        let (device, queue) = adapter.request_device(descriptor, None).await.unwrap();
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+        label: Some("projector_bind_group_layout"),
+    })
+}
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-        };
-        surface.configure(&device, &config);
+/// An array texture, its sampler and a uniform saying which layers to use, bind group 3 of the
+/// flipbook and projector blending pipelines
+fn create_texture_array_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+        label: Some("texture_array_bind_group_layout"),
+    })
+}
 
-        let texture_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-                label: Some("texture_bind_group_layout"),
-            });
+/// Uniform with the id of the mesh drawn, at a dynamic offset per mesh, bind group 3 of the
+/// picking pipeline
+#[cfg(not(target_arch = "wasm32"))]
+fn create_pick_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(PICK_ID_SIZE),
+            },
+            count: None,
+        }],
+        label: Some("pick_bind_group_layout"),
+    })
+}
 
-        let sensor_size = 24_f32;
-        let focal_length = 50_f32;
-        let fovy = 2.0 * ((sensor_size / focal_length) * 0.5).atan();
-        let camera = camera::Camera {
-            eye: [-6.0, 6.0, 6.0].into(),
-            target: [0.0, 0.0, 0.0].into(),
-            up: glam::Vec3::Y,
-            aspect: config.width as f32 / config.height as f32,
-            fovy,
-            znear: 0.1,
-            zfar: 100.0,
-        };
+/// Settings that differ between the render pipeline variants
+struct PipelineConfig<'a> {
+    label: &'a str,
+    /// Entry point in the built-in shader
+    vs_entry_point: &'a str,
+    /// Layout of the vertex buffer in slot 0, which the instances follow in slot 1
+    vertex_layout: wgpu::VertexBufferLayout<'static>,
+    /// Module holding `fs_entry_point`, if not the one holding `vs_entry_point`
+    fs_module: Option<&'a wgpu::ShaderModule>,
+    fs_entry_point: &'a str,
+    topology: wgpu::PrimitiveTopology,
+    polygon_mode: wgpu::PolygonMode,
+    depth_bias: wgpu::DepthBiasState,
+    /// `None` for formats that can't be blended, like the picking target's
+    blend: Option<wgpu::BlendState>,
+    /// Shade every pixel a triangle touches, not only those whose center it covers
+    conservative: bool,
+    /// Samples per pixel of the color and depth attachments
+    sample_count: u32,
+    /// Color targets after the one in the pipeline's format, see [`GBUFFER_FORMATS`]
+    extra_targets: &'a [Option<wgpu::ColorTargetState>],
+    /// `Cw` for mirrored instances, see [`Instance::is_mirrored`]
+    front_face: wgpu::FrontFace,
+}
 
-        let camera_controller = camera::CameraController::new(0.2);
+impl Default for PipelineConfig<'_> {
+    fn default() -> Self {
+        Self {
+            label: "Render Pipeline",
+            vs_entry_point: "vs_main",
+            vertex_layout: model::ModelVertex::desc(),
+            fs_module: None,
+            fs_entry_point: "fs_main",
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            depth_bias: wgpu::DepthBiasState::default(),
+            blend: Some(wgpu::BlendState::REPLACE),
+            conservative: false,
+            sample_count: 1,
+            extra_targets: &[],
+            front_face: wgpu::FrontFace::Ccw,
+        }
+    }
+}
 
-        let mut camera_uniform = camera::CameraUniform::new();
-        camera_uniform.update_view_proj(&camera);
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    config: &PipelineConfig,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(config.label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: config.vs_entry_point,
+            buffers: &[config.vertex_layout.clone(), InstanceRaw::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: config.fs_module.unwrap_or(shader),
+            entry_point: config.fs_entry_point,
+            targets: &[
+                &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: config.blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                config.extra_targets,
+            ]
+            .concat(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: config.topology,
+            strip_index_format: None,
+            front_face: config.front_face,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: config.polygon_mode,
+            unclipped_depth: false,
+            conservative: config.conservative,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: texture::Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: config.depth_bias,
+        }),
+        multisample: wgpu::MultisampleState {
+            count: config.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
 
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Camera Buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+/// Fragment shader module with an `fs_main` from the user supplied WGSL `source` to use in
+/// place of the built-in one, checked by building a pipeline with it.  It is paired with the
+/// built-in `vs_main`, so it must declare:
+///
+/// - `@group(0) @binding(0) var t_diffuse: texture_2d<f32>;` and
+///   `@group(0) @binding(1) var s_diffuse: sampler;`, the projected image
+/// - optionally `@group(1) @binding(0) var<uniform> camera: CameraUniform;` with the same
+///   layout as in `shader.wgsl`
+/// - optionally `@group(2) @binding(0) var<uniform> projector: ProjectorUniform;`, likewise,
+///   and `@group(2) @binding(1) var<uniform> light: LightUniform;`
+/// - `@fragment fn fs_main(@location(0) projector_position: vec4<f32>) -> @location(0)
+///   vec4<f32>`, taking the fragment's position in projector clip space
+async fn create_custom_fragment_shader(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    source: &str,
+) -> anyhow::Result<wgpu::ShaderModule> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let fs_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Custom Fragment Shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    create_render_pipeline(
+        device,
+        layout,
+        shader,
+        format,
+        &PipelineConfig {
+            label: "Custom Fragment Pipeline",
+            fs_module: Some(&fs_module),
+            ..Default::default()
+        },
+    );
+    match device.pop_error_scope().await {
+        Some(e) => anyhow::bail!("Custom fragment shader doesn't fit the projection pipeline: {e}"),
+        None => Ok(fs_module),
+    }
+}
 
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                ],
-                label: Some("camera_bind_group_layout"),
-            });
+/// The image given in the options, or the test pattern
+async fn load_image(
+    options: &SceneOptions,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    oversized: texture::Oversized,
+) -> anyhow::Result<texture::Texture> {
+    match &options.image {
+        Some(path) => resources::load_texture_file(path, device, queue, oversized).await,
+        None => {
+            let file_name = "image_projection_test_square.png";
+            resources::load_texture(file_name, device, queue, oversized).await
+        }
+    }
+}
 
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &camera_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: camera_buffer.as_entire_binding(),
+/// Fills the current viewport with the color held in a one-instance vertex buffer.  Used to give
+/// a letterboxed scene its own background, since clearing always covers the whole attachment.
+fn create_background_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Background Pipeline Layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Background Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_background",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x4],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_background",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        // Behind everything, without touching the depth buffer
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: texture::Texture::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+/// What the render pipelines are built from, kept to rebuild them for another sample count
+struct PipelineSources {
+    shader: wgpu::ShaderModule,
+    /// Replaces `fs_main`, see [`create_custom_fragment_shader`]
+    custom_fragment: Option<wgpu::ShaderModule>,
+    layout: wgpu::PipelineLayout,
+    /// Adds the array texture group of the flipbook and projector blending
+    texture_array_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    conservative: bool,
+    /// The adapter supports `POLYGON_MODE_LINE` for the wire overlay and wireframe mode
+    wire_supported: bool,
+    /// Adds the mesh id group of picking, see [`Renderer::pick_gpu`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pick_layout: wgpu::PipelineLayout,
+}
+
+/// Every pipeline drawing the scene for one winding of its triangles, see
+/// [`Instance::is_mirrored`].  Those drawing into the color attachments all have to agree on
+/// the sample count.
+struct Pipelines {
+    fill: wgpu::RenderPipeline,
+    premultiplied: wgpu::RenderPipeline,
+    flipbook: wgpu::RenderPipeline,
+    blend: wgpu::RenderPipeline,
+    /// How many projectors cover each fragment, see [`State::set_coverage_debug`]: `fs_coverage`
+    /// over the projector set and `fs_coverage_single` for the scene's projector alone
+    coverage: wgpu::RenderPipeline,
+    coverage_single: wgpu::RenderPipeline,
+    wire: Option<wgpu::RenderPipeline>,
+    wireframe: Option<wgpu::RenderPipeline>,
+    /// [`model::LineMesh`]es like [`cube::FrustumLines`] and [`cube::Grid`]
+    lines: wgpu::RenderPipeline,
+    /// Alpha blended [`Billboards`]
+    billboard: wgpu::RenderPipeline,
+    background: wgpu::RenderPipeline,
+    /// Mesh ids and world positions for [`Renderer::pick_gpu`], never multisampled
+    #[cfg(not(target_arch = "wasm32"))]
+    pick: wgpu::RenderPipeline,
+    /// See [`Renderer::render_gbuffer`], never multisampled
+    #[cfg(not(target_arch = "wasm32"))]
+    gbuffer: wgpu::RenderPipeline,
+}
+
+impl Pipelines {
+    fn new(
+        device: &wgpu::Device,
+        sources: &PipelineSources,
+        sample_count: u32,
+        front_face: wgpu::FrontFace,
+    ) -> Self {
+        let fill = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                fs_module: sources.custom_fragment.as_ref(),
+                conservative: sources.conservative,
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        // Drawn on top of the filled mesh, pulled slightly towards the camera to avoid z-fighting
+        let wire = sources.wire_supported.then(|| {
+            create_render_pipeline(
+                device,
+                &sources.layout,
+                &sources.shader,
+                sources.format,
+                &PipelineConfig {
+                    label: "Wire Overlay Pipeline",
+                    fs_entry_point: "fs_wire",
+                    polygon_mode: wgpu::PolygonMode::Line,
+                    depth_bias: wgpu::DepthBiasState {
+                        constant: -2,
+                        slope_scale: -1.0,
+                        clamp: 0.0,
+                    },
+                    sample_count,
+                    front_face,
+                    ..Default::default()
                 },
-            ],
-            label: Some("camera_bind_group"),
+            )
         });
+        // Only the edges, still showing the projected image, instead of filled triangles
+        let wireframe = sources.wire_supported.then(|| {
+            create_render_pipeline(
+                device,
+                &sources.layout,
+                &sources.shader,
+                sources.format,
+                &PipelineConfig {
+                    label: "Wireframe Pipeline",
+                    fs_module: sources.custom_fragment.as_ref(),
+                    polygon_mode: wgpu::PolygonMode::Line,
+                    sample_count,
+                    front_face,
+                    ..Default::default()
+                },
+            )
+        });
+        let lines = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Line Pipeline",
+                vs_entry_point: "vs_lines",
+                vertex_layout: model::LineVertex::desc(),
+                fs_entry_point: "fs_lines",
+                topology: wgpu::PrimitiveTopology::LineList,
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        // Composites images with soft alpha edges without the dark fringe of straight alpha
+        let premultiplied = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Premultiplied Alpha Pipeline",
+                fs_entry_point: "fs_premultiplied",
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        // The flipbook and projector blending pipelines each fill the last group with their own
+        // array texture
+        let flipbook = create_render_pipeline(
+            device,
+            &sources.texture_array_layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Flipbook Pipeline",
+                fs_entry_point: "fs_flipbook",
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        let blend = create_render_pipeline(
+            device,
+            &sources.texture_array_layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Projector Blending Pipeline",
+                fs_entry_point: "fs_blend",
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        let coverage = create_render_pipeline(
+            device,
+            &sources.texture_array_layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Coverage Pipeline",
+                fs_entry_point: "fs_coverage",
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        let coverage_single = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Single Projector Coverage Pipeline",
+                fs_entry_point: "fs_coverage_single",
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        let billboard = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "Billboard Pipeline",
+                vs_entry_point: "vs_billboard",
+                fs_entry_point: "fs_billboard",
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                sample_count,
+                front_face,
+                ..Default::default()
+            },
+        );
+        let background =
+            create_background_pipeline(device, &sources.shader, sources.format, sample_count);
+        #[cfg(not(target_arch = "wasm32"))]
+        let pick = create_render_pipeline(
+            device,
+            &sources.pick_layout,
+            &sources.shader,
+            PICK_FORMAT,
+            &PipelineConfig {
+                label: "Pick Pipeline",
+                fs_entry_point: "fs_pick",
+                blend: None,
+                front_face,
+                ..Default::default()
+            },
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        let gbuffer = create_render_pipeline(
+            device,
+            &sources.layout,
+            &sources.shader,
+            sources.format,
+            &PipelineConfig {
+                label: "G-Buffer Pipeline",
+                fs_entry_point: "fs_gbuffer",
+                extra_targets: &GBUFFER_FORMATS.map(|format| Some(format.into())),
+                front_face,
+                ..Default::default()
+            },
+        );
+        Self {
+            fill,
+            premultiplied,
+            flipbook,
+            blend,
+            coverage,
+            coverage_single,
+            wire,
+            wireframe,
+            lines,
+            billboard,
+            background,
+            #[cfg(not(target_arch = "wasm32"))]
+            pick,
+            #[cfg(not(target_arch = "wasm32"))]
+            gbuffer,
+        }
+    }
+}
 
-        let clear_color = wgpu::Color {
-            r: 0.1,
-            g: 0.2,
-            b: 0.3,
-            a: 1.0,
-        };
+/// `count` if the adapter can multisample both `format` and the depth format that many times,
+/// otherwise 1
+fn supported_sample_count(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    count: u32,
+) -> u32 {
+    // WebGPU only guarantees 4 besides 1, other counts are adapter specific
+    let adapter_specific = device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let multisampled = |format| {
+        adapter
+            .get_texture_format_features(format)
+            .flags
+            .sample_count_supported(count)
+    };
+    let supported = (count == 4 || adapter_specific)
+        && multisampled(format)
+        && multisampled(texture::Texture::DEPTH_FORMAT);
+    if count == 1 || supported {
+        count
+    } else {
+        warn!("{count}x multisampling is not supported for {format:?}, falling back to 1");
+        1
+    }
+}
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
+/// Color attachment resolved into the surface when multisampling, `None` for one sample
+fn create_msaa_target(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+) -> Option<texture::Texture> {
+    (sample_count > 1).then(|| {
+        texture::Texture::create_multisampled_target(device, config, sample_count, "msaa_target")
+    })
+}
 
-        let depth_texture =
-            texture::Texture::create_depth_texture(&device, &config, "depth_texture");
+/// Which camera a [`Viewport`] or window renders from.
+pub enum CameraSource {
+    /// The interactive camera driven by the `CameraController`
+    Main,
+    /// A static camera, e.g. a snapshot of the main one
+    Fixed(camera::Camera),
+    /// The scene's projector, e.g. for an output window shown on the physical projector.  Its
+    /// image fills the window or viewport whatever their aspect ratio.
+    Projector,
+}
 
-        let pipeline_layout_desc = &wgpu::PipelineLayoutDescriptor {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
-            push_constant_ranges: &[],
-        };
-        let pipeline_layout = device.create_pipeline_layout(pipeline_layout_desc);
+/// A sub-rectangle of the window the scene is rendered into.
+pub struct Viewport {
+    /// `[x, y, width, height]` as fractions of the window size
+    pub rect: [f32; 4],
+    pub camera_uniform_source: CameraSource,
+}
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[cube::SimpleVertex::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: texture::Texture::DEPTH_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+impl Viewport {
+    /// Pixel rectangle `(x, y, width, height)` of the viewport in a surface of the given size
+    fn pixel_rect(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let [x, y, w, h] = self.rect;
+        let x = ((x * width as f32) as u32).min(width - 1);
+        let y = ((y * height as f32) as u32).min(height - 1);
+        let w = ((w * width as f32) as u32).clamp(1, width - x);
+        let h = ((h * height as f32) as u32).clamp(1, height - y);
+        (x, y, w, h)
+    }
+}
 
-        let texture = resources::load_texture("image_projection_test_square.png", &device, &queue)
-            .await
-            .unwrap();
-        let material = model::Material::new(
-            "image_projection",
-            texture,
-            &device,
-            &texture_bind_group_layout,
+struct ViewportTarget {
+    viewport: Viewport,
+    uniform: camera::CameraUniform,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+/// A window the [`Renderer`] draws into: the main window, or a secondary one sharing its device
+/// and pipelines, e.g. a projector output on a second display, which renders the scene from its
+/// own fixed camera.
+struct WindowContext {
+    surface: wgpu::Surface,
+    config: wgpu::SurfaceConfiguration,
+    depth_texture: texture::Texture,
+    /// Multisampled color attachment, if the sample count is above 1
+    msaa_target: Option<texture::Texture>,
+    camera: CameraSource,
+    camera_uniform: camera::CameraUniform,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    // Declared after `surface` so the surface is dropped first
+    window: Window,
+}
+
+impl WindowContext {
+    /// Configure `surface`, created for `window`, with `config` and create the window's
+    /// attachments and camera buffer
+    fn new(
+        renderer: &Renderer,
+        window: Window,
+        surface: wgpu::Surface,
+        config: wgpu::SurfaceConfiguration,
+        camera: CameraSource,
+    ) -> Self {
+        let device = &renderer.device;
+        surface.configure(device, &config);
+        let depth_texture = texture::Texture::create_depth_texture(
+            device,
+            &config,
+            renderer.sample_count,
+            "depth_texture",
         );
-        let cube_model = cube::Cube::new("test_cube", &device).into();
-        let plane_model = cube::Plane::new("test_plane", &device).into();
+        let msaa_target = create_msaa_target(device, &config, renderer.sample_count);
+
+        let mut camera_uniform = camera::CameraUniform::new();
+        renderer.update_camera_uniform(&camera, None, &mut camera_uniform);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &renderer.camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("camera_bind_group"),
+        });
 
         Self {
-            window,
             surface,
-            device,
-            queue,
             config,
-            size,
-            clear_color,
-            pipeline,
+            depth_texture,
+            msaa_target,
             camera,
-            camera_controller,
             camera_uniform,
             camera_buffer,
             camera_bind_group,
-            depth_texture,
-            meshes: vec![cube_model, plane_model],
-            materials: vec![material],
+            window,
         }
     }
 
-    pub fn window(&self) -> &Window {
-        &self.window
+    fn request_redraw(&self) {
+        self.window.request_redraw();
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    /// Size of the surface, which is never 0
+    fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    /// Recreate the depth and multisampled attachments for the surface size and the renderer's
+    /// sample count
+    fn create_attachments(&mut self, renderer: &Renderer) {
+        self.depth_texture = texture::Texture::create_depth_texture(
+            &renderer.device,
+            &self.config,
+            renderer.sample_count,
+            "depth_texture",
+        );
+        self.msaa_target =
+            create_msaa_target(&renderer.device, &self.config, renderer.sample_count);
+    }
+
+    /// Reconfigure the surface for `new_size`.  A fixed camera takes the new aspect ratio.
+    fn resize(&mut self, renderer: &Renderer, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-            self.depth_texture =
-                texture::Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
-            self.camera.aspect = self.config.width as f32 / self.config.height as f32;
+            self.surface.configure(&renderer.device, &self.config);
+            self.create_attachments(renderer);
+            if let CameraSource::Fixed(camera) = &mut self.camera {
+                camera.aspect = new_size.width as f32 / new_size.height as f32;
+            }
+            self.write_camera(renderer);
         }
     }
 
-    pub fn input(&mut self, event: &WindowEvent) -> bool {
+    /// Upload the camera the window renders from
+    fn write_camera(&mut self, renderer: &Renderer) {
+        renderer.update_camera_uniform(&self.camera, None, &mut self.camera_uniform);
+        renderer.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
+    }
+}
+
+/// Image sequence projected in place of the active material, one array layer per frame
+struct Flipbook {
+    texture: texture::Texture,
+    frame_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Line list mesh drawn over the scene, like the projector's frustum
+struct LineOverlay {
+    mesh: model::LineMesh,
+    /// Places the mesh once, rather than per instance like the scene's meshes
+    instance_buffer: wgpu::Buffer,
+}
+
+/// Copies of an image at many points, each turned to face the camera, see
+/// [`State::add_billboards`]
+struct Billboards {
+    /// [`cube::Billboard::unit`] showing the image
+    mesh: model::Mesh,
+    material: model::Material,
+    positions: Vec<glam::Vec3>,
+    size: f32,
+    /// Rewritten for every frame by [`Renderer::face_billboards`]
+    instance_buffer: wgpu::Buffer,
+}
+
+/// Images and uniforms of the projectors in `Scene::projectors`, see
+/// [`Renderer::add_projector`]
+struct ProjectorSet {
+    /// Layer `i` holds the image of projector `i`
+    images: wgpu::Texture,
+    /// Projector count followed by a `ProjectorUniform` per projector
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// What a frame is made of, one value per pixel, see [`State::capture_gbuffer`]
+#[cfg(not(target_arch = "wasm32"))]
+pub struct GBuffer {
+    /// The frame as drawn with the scene's projector
+    pub color: image::RgbaImage,
+    /// World space normal in `rgb`, alpha 1 where a mesh is drawn and 0 elsewhere
+    pub normal: image::Rgba32FImage,
+    /// Texture coordinates in the projector's image, -1 where it doesn't cover the surface
+    pub uv: image::ImageBuffer<image::LumaA<f32>, Vec<f32>>,
+    /// World space distance from the camera's eye, 0 where no mesh is drawn
+    pub depth: image::ImageBuffer<image::Luma<f32>, Vec<f32>>,
+}
+
+/// The device and everything drawn with it: the pipelines, the scene and its overlays.  It
+/// doesn't need a window, see [`Renderer::render_to_texture`]; windows draw with it through
+/// their [`WindowContext`].
+struct Renderer {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipelines: Pipelines,
+    /// Draw the instances after `Instance::is_mirrored` ones, see [`Renderer::windings`]
+    mirrored_pipelines: Pipelines,
+    pipeline_sources: PipelineSources,
+    /// Samples per pixel, see [`State::set_sample_count`]
+    sample_count: u32,
+    texture_array_bind_group_layout: wgpu::BindGroupLayout,
+    flipbook: Option<Flipbook>,
+    projector_set: Option<ProjectorSet>,
+    /// See [`State::toggle_frustum`]
+    frustum: Option<LineOverlay>,
+    /// See [`State::measure`]
+    measurement: Option<LineOverlay>,
+    /// See [`State::toggle_grid`]
+    grid: Option<LineOverlay>,
+    /// See [`State::add_billboards`]
+    billboards: Vec<Billboards>,
+    /// Every mesh is drawn once per instance
+    instances: Vec<Instance>,
+    instance_buffer: wgpu::Buffer,
+    wire_overlay: bool,
+    /// Draw edges instead of filled triangles
+    wireframe: bool,
+    /// Color meshes by how many projectors cover them instead of projecting
+    coverage_debug: bool,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    /// Last uploaded to `projector_buffer`
+    projector_uniform: camera::ProjectorUniform,
+    projector_buffer: wgpu::Buffer,
+    light_buffer: wgpu::Buffer,
+    /// Holds both the projector and the light
+    projector_bind_group: wgpu::BindGroup,
+    viewports: Vec<ViewportTarget>,
+    /// A file is being dragged over the main window
+    file_hovered: bool,
+    scene: Scene,
+    /// Width / height the scene is fitted to, leaving bars at the window edges
+    target_aspect: Option<f32>,
+    letterbox_color: wgpu::Color,
+    background_buffer: wgpu::Buffer,
+    /// How images too large for the device are loaded
+    oversized: texture::Oversized,
+    /// Mesh ids of [`Renderer::pick_gpu`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pick_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+/// The scene, its main window and any output windows, driven by the event loop.  Handed to the
+/// callback of [`run_with_update`] once per frame to inspect and change what is rendered.
+pub struct State {
+    renderer: Renderer,
+    window: WindowContext,
+    outputs: Vec<WindowContext>,
+    continuous: bool,
+    camera_controller: camera::CameraController,
+    last_stats: RenderStats,
+    frame_counter: stats::FrameCounter,
+    idle_timeout: Option<std::time::Duration>,
+    last_input: instant::Instant,
+    fps_cap: Option<u32>,
+    last_frame: instant::Instant,
+    scale_factor: f64,
+    /// Last cursor position over the main window, `None` once it leaves
+    cursor_position: Option<PhysicalPosition<f64>>,
+    /// Cursor motion since the last update, in physical pixels
+    cursor_delta: glam::Vec2,
+    /// The right mouse button is held, dragging orbits the camera
+    mouse_pressed: bool,
+    /// Modifier keys held, e.g. Shift turns the arrows to the lens shift
+    modifiers: ModifiersState,
+    /// Takes over dragging and scrolling from `camera_controller`, see `SceneOptions::orbit`
+    orbit_controller: Option<camera::OrbitController>,
+    /// Drives the projected image through the loaded materials, one per frame
+    timeline: Option<Timeline>,
+    /// Moves the scene's projector, see [`State::animate_projector`]
+    projector_animation: Option<camera::ProjectorAnimation>,
+    /// First point of a measurement Shift+clicked so far
+    measure_start: Option<glam::Vec3>,
+    /// Distance of the measurement shown, see [`State::measure`]
+    measured: Option<f32>,
+    last_update: instant::Instant,
+}
+
+impl Renderer {
+    /// Device on `adapter` with the pipelines drawing in `format`, and the scene loaded from
+    /// `options`, its camera taking the `aspect` ratio
+    async fn new(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        format: wgpu::TextureFormat,
+        options: &SceneOptions,
+        aspect: f32,
+    ) -> anyhow::Result<Self> {
+        let wire_supported = adapter
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE);
+        if !wire_supported {
+            warn!("Adapter does not support POLYGON_MODE_LINE, wire overlay is disabled");
+        }
+        let conservative = options.conservative
+            && adapter
+                .features()
+                .contains(wgpu::Features::CONSERVATIVE_RASTERIZATION);
+        if options.conservative && !conservative {
+            warn!("Adapter does not support CONSERVATIVE_RASTERIZATION, ignoring it");
+        }
+        // Compressed DDS images are decoded on the CPU when BC compression is missing
+        let optional_features = (wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::CONSERVATIVE_RASTERIZATION)
+            & adapter.features();
+        let descriptor = &wgpu::DeviceDescriptor {
+            features: if wire_supported {
+                wgpu::Features::POLYGON_MODE_LINE | optional_features
+            } else {
+                optional_features
+            },
+            limits: if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::default()
+            },
+            label: None,
+        };
+        let (device, queue) = adapter.request_device(descriptor, None).await.unwrap();
+
+        let texture_bind_group_layout = create_texture_bind_group_layout(&device);
+
+        let sensor_size = 24_f32;
+        let focal_length = 50_f32;
+        let fovy = match options.fov {
+            Some(fov) => fov.to_radians(),
+            None => 2.0 * ((sensor_size / focal_length) * 0.5).atan(),
+        };
+        let camera = camera::Camera::new(
+            options.eye.unwrap_or(glam::vec3(-6.0, 6.0, 6.0)),
+            [0.0, 0.0, 0.0],
+            glam::Vec3::Y,
+            aspect,
+            fovy,
+        );
+
+        let camera_bind_group_layout = create_camera_bind_group_layout(&device);
+
+        // Starts out projecting from the initial viewpoint, see `Scene::new`
+        let projector_uniform =
+            camera::ProjectorUniform::from(&camera::Projector::from_camera(&camera));
+        let projector_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Projector Buffer"),
+            contents: bytemuck::cast_slice(&[projector_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&[light::LightUniform::from(&light::Light::DEFAULT)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let projector_bind_group_layout = create_projector_bind_group_layout(&device);
+        let projector_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &projector_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: projector_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("projector_bind_group"),
+        });
+
+        let clear_color = color::srgb_color_to_linear(options.clear_color.unwrap_or(wgpu::Color {
+            r: 0.1,
+            g: 0.2,
+            b: 0.3,
+            a: 1.0,
+        }));
+
+        // Report WGSL and pipeline validation errors with their labels instead of panicking, so
+        // a broken shader edit doesn't take the whole app down
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let pipeline_layout_desc = &wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &texture_bind_group_layout,
+                &camera_bind_group_layout,
+                &projector_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        };
+        let pipeline_layout = device.create_pipeline_layout(pipeline_layout_desc);
+
+        let custom_fragment = match &options.fragment_shader {
+            Some(source) => Some(
+                create_custom_fragment_shader(&device, &pipeline_layout, &shader, format, source)
+                    .await?,
+            ),
+            None => None,
+        };
+        let texture_array_bind_group_layout = create_texture_array_bind_group_layout(&device);
+        let texture_array_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Texture Array Pipeline Layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &projector_bind_group_layout,
+                    &texture_array_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        #[cfg(not(target_arch = "wasm32"))]
+        let pick_bind_group_layout = create_pick_bind_group_layout(&device);
+        let pipeline_sources = PipelineSources {
+            shader,
+            custom_fragment,
+            layout: pipeline_layout,
+            texture_array_layout: texture_array_pipeline_layout,
+            format,
+            conservative,
+            wire_supported,
+            #[cfg(not(target_arch = "wasm32"))]
+            pick_layout: device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Pick Pipeline Layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &camera_bind_group_layout,
+                    &projector_bind_group_layout,
+                    &pick_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            }),
+        };
+        // Multisampling is switched on afterwards, see `set_sample_count`
+        let sample_count = 1;
+        let pipelines = Pipelines::new(
+            &device,
+            &pipeline_sources,
+            sample_count,
+            wgpu::FrontFace::Ccw,
+        );
+        let mirrored_pipelines = Pipelines::new(
+            &device,
+            &pipeline_sources,
+            sample_count,
+            wgpu::FrontFace::Cw,
+        );
+
+        if let Some(e) = device.pop_error_scope().await {
+            anyhow::bail!("Failed to create the render pipelines: {e}");
+        }
+        let background_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Background Buffer"),
+            size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let oversized = if options.downscale_oversized {
+            texture::Oversized::Downscale
+        } else {
+            texture::Oversized::Fail
+        };
+        let texture = load_image(options, &device, &queue, oversized).await?;
+        let material = model::Material::new(
+            "image_projection",
+            texture,
+            &device,
+            &texture_bind_group_layout,
+        );
+        let instance_buffer = create_instance_buffer(&device, &[Instance::IDENTITY]);
+        let mut scene = Scene::new(camera, material, clear_color);
+        match &options.model {
+            Some(file_name) => {
+                let model =
+                    resources::load_model(file_name, &device, &queue, &texture_bind_group_layout)
+                        .await?;
+                if let Some(aabb) = model.aabb() {
+                    log::info!(
+                        "Loaded {file_name} centered on {} with extents {}",
+                        aabb.center(),
+                        aabb.extents()
+                    );
+                }
+                for mesh in model.meshes {
+                    scene.add_mesh(mesh);
+                }
+                // Its own textures can be projected too
+                for material in model.materials {
+                    scene.add_material(material);
+                }
+            }
+            None => {
+                scene.add_mesh(cube::Plane::new("test_plane", &device).into());
+            }
+        }
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            pipelines,
+            mirrored_pipelines,
+            pipeline_sources,
+            sample_count,
+            texture_array_bind_group_layout,
+            flipbook: None,
+            projector_set: None,
+            frustum: None,
+            measurement: None,
+            grid: None,
+            billboards: Vec::new(),
+            instances: vec![Instance::IDENTITY],
+            instance_buffer,
+            wire_overlay: false,
+            wireframe: false,
+            coverage_debug: false,
+            camera_bind_group_layout,
+            texture_bind_group_layout,
+            projector_uniform,
+            projector_buffer,
+            light_buffer,
+            projector_bind_group,
+            viewports: Vec::new(),
+            file_hovered: false,
+            scene,
+            target_aspect: None,
+            letterbox_color: wgpu::Color::BLACK,
+            background_buffer,
+            oversized,
+            #[cfg(not(target_arch = "wasm32"))]
+            pick_bind_group_layout,
+        })
+    }
+
+    /// Point `uniform` at what a window or viewport rendering from `source` sees, a camera
+    /// taking `aspect` if given
+    fn update_camera_uniform(
+        &self,
+        source: &CameraSource,
+        aspect: Option<f32>,
+        uniform: &mut camera::CameraUniform,
+    ) {
+        let mut camera = match source {
+            CameraSource::Main => self.scene.camera,
+            CameraSource::Fixed(camera) => *camera,
+            CameraSource::Projector => {
+                return uniform.update_from_projector(&self.scene.projector);
+            }
+        };
+        if let Some(aspect) = aspect {
+            camera.aspect = aspect;
+        }
+        uniform.update_view_proj(&camera);
+    }
+
+    /// Centered pixel rectangle `(x, y, width, height)` of a `width` × `height` target matching
+    /// the target aspect, if one is set
+    fn letterbox_rect(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let aspect = self.target_aspect?;
+        if width as f32 / height as f32 > aspect {
+            let w = ((height as f32 * aspect) as u32).clamp(1, width);
+            Some(((width - w) / 2, 0, w, height))
+        } else {
+            let h = ((width as f32 / aspect) as u32).clamp(1, height);
+            Some((0, (height - h) / 2, width, h))
+        }
+    }
+
+    /// Scene background color
+    fn background_color(&self) -> wgpu::Color {
+        // Brighten the background while a file is dragged over the window
+        if self.file_hovered {
+            wgpu::Color {
+                r: self.scene.background.r + 0.2,
+                g: self.scene.background.g + 0.2,
+                b: self.scene.background.b + 0.2,
+                a: self.scene.background.a,
+            }
+        } else {
+            self.scene.background
+        }
+    }
+
+    /// Project the image from `projector` instead
+    pub fn set_projector(&mut self, projector: camera::Projector) {
+        self.scene.projector = projector;
+        self.projector_uniform.update(&projector);
+        self.queue.write_buffer(
+            &self.projector_buffer,
+            0,
+            bytemuck::cast_slice(&[self.projector_uniform]),
+        );
+        if self.frustum.is_some() {
+            self.frustum = Some(self.frustum_outline());
+        }
+    }
+
+    fn frustum_outline(&self) -> LineOverlay {
+        let corners = self.scene.projector.frustum_corners();
+        LineOverlay {
+            mesh: cube::FrustumLines::new("projector_frustum", corners, &self.device).into(),
+            instance_buffer: create_instance_buffer(&self.device, &[Instance::IDENTITY]),
+        }
+    }
+
+    /// Draw `texture` on a `size` × `size` square at each of `positions`, always facing the
+    /// camera, see [`Instance::facing`].  An empty list adds nothing.
+    pub fn add_billboards(
+        &mut self,
+        positions: &[glam::Vec3],
+        size: f32,
+        texture: texture::Texture,
+    ) {
+        if positions.is_empty() {
+            return;
+        }
+        let instances: Vec<_> = positions
+            .iter()
+            .map(|&position| Instance::facing(&self.scene.camera, position, size))
+            .collect();
+        self.billboards.push(Billboards {
+            mesh: cube::Billboard::unit("billboard", &self.device).into(),
+            material: model::Material::new(
+                "billboard",
+                texture,
+                &self.device,
+                &self.texture_bind_group_layout,
+            ),
+            positions: positions.to_vec(),
+            size,
+            instance_buffer: create_instance_buffer(&self.device, &instances),
+        });
+    }
+
+    /// Stamp `image` onto the surface at `position` facing `normal` with a small projector
+    /// aimed at it along the normal, see [`camera::Projector::decal`].  Decals accumulate as
+    /// projectors of their own, kept live in `Scene::projectors` rather than baked into the
+    /// mesh's texture.  The first one brings the scene's projector into the set, as added
+    /// projectors replace it, so `image` must have the size and format of the projected image.
+    fn place_decal(
+        &mut self,
+        position: glam::Vec3,
+        normal: glam::Vec3,
+        image: texture::Texture,
+        size: f32,
+    ) -> anyhow::Result<()> {
+        if self.projector_set.is_none() {
+            let current = self
+                .scene
+                .active_material()
+                .diffuse_texture
+                .duplicate(&self.device, &self.queue);
+            self.add_projector(self.scene.projector, current)?;
+        }
+        let decal = camera::Projector {
+            aspect: image.width() as f32 / image.height() as f32,
+            ..camera::Projector::decal(position, normal, size)
+        };
+        self.add_projector(decal, image)
+    }
+
+    /// Turn the billboards to face the scene's camera as it is now, which split views see too
+    fn face_billboards(&self) {
+        for billboards in &self.billboards {
+            let raw: Vec<_> = billboards
+                .positions
+                .iter()
+                .map(|&position| {
+                    Instance::facing(&self.scene.camera, position, billboards.size).to_raw()
+                })
+                .collect();
+            self.queue
+                .write_buffer(&billboards.instance_buffer, 0, bytemuck::cast_slice(&raw));
+        }
+    }
+
+    /// Point the light along `direction`, which doesn't have to be normalized
+    pub fn set_light_direction(&mut self, direction: glam::Vec3) {
+        self.scene.light.direction = direction;
+        self.queue.write_buffer(
+            &self.light_buffer,
+            0,
+            bytemuck::cast_slice(&[light::LightUniform::from(&self.scene.light)]),
+        );
+    }
+
+    /// Blend `projector`, showing `texture`, with the projectors added before.  Once any are
+    /// added they replace the scene's projector.  Where several cover a surface their images
+    /// are averaged, weighted by `Projector::weight`, so overlaps aren't brighter than the rest.
+    /// Every image must have the size and format of the first one.
+    pub fn add_projector(
+        &mut self,
+        projector: camera::Projector,
+        texture: texture::Texture,
+    ) -> anyhow::Result<()> {
+        let (mut projectors, size, format) = match &self.projector_set {
+            Some(set) => (
+                self.scene.projectors.clone(),
+                set.images.size(),
+                set.images.format(),
+            ),
+            None => (Vec::new(), texture.size, texture.format),
+        };
+        if projectors.len() == MAX_PROJECTORS {
+            anyhow::bail!("At most {MAX_PROJECTORS} projectors can be blended");
+        }
+        // There is no last good matrix to fall back on for a projector that's new to the set
+        if !projector.build_view_projection_matrix().is_finite() {
+            anyhow::bail!("Projector has a non-finite view projection");
+        }
+        if (texture.width(), texture.height(), texture.format) != (size.width, size.height, format)
+        {
+            anyhow::bail!(
+                "Projector image is {}x{} {:?}, the others are {}x{} {format:?}",
+                texture.width(),
+                texture.height(),
+                texture.format,
+                size.width,
+                size.height
+            );
+        }
+        projectors.push(projector);
+        let layer_size = wgpu::Extent3d {
+            depth_or_array_layers: 1,
+            ..texture.size
+        };
+
+        // Grow the array by a layer, copying over the images already in it.  The GL backend
+        // treats a texture with a single layer as a plain 2D one, which samples black through
+        // the array view, so there are always at least two.
+        let images = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Projector Images"),
+            size: wgpu::Extent3d {
+                depth_or_array_layers: projectors.len().max(2) as u32,
+                ..layer_size
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Projector Images Encoder"),
+            });
+        if let Some(set) = &self.projector_set {
+            encoder.copy_texture_to_texture(
+                set.images.as_image_copy(),
+                images.as_image_copy(),
+                set.images.size(),
+            );
+        }
+        encoder.copy_texture_to_texture(
+            texture.texture.as_image_copy(),
+            wgpu::ImageCopyTexture {
+                texture: &images,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: projectors.len() as u32 - 1,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            layer_size,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer = match self.projector_set.take() {
+            Some(set) => set.buffer,
+            None => self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Projector Set Buffer"),
+                size: (std::mem::size_of::<[u32; 4]>()
+                    + MAX_PROJECTORS * std::mem::size_of::<camera::ProjectorUniform>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+        };
+        let count = [projectors.len() as u32, 0, 0, 0];
+        let uniforms: Vec<camera::ProjectorUniform> = projectors.iter().map(Into::into).collect();
+        self.queue
+            .write_buffer(&buffer, 0, bytemuck::cast_slice(&count));
+        self.queue.write_buffer(
+            &buffer,
+            std::mem::size_of_val(&count) as wgpu::BufferAddress,
+            bytemuck::cast_slice(&uniforms),
+        );
+
+        let view = images.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_array_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("projector_set_bind_group"),
+        });
+        self.scene.projectors = projectors;
+        self.projector_set = Some(ProjectorSet {
+            images,
+            buffer,
+            bind_group,
+        });
+        Ok(())
+    }
+
+    /// The pipelines for instances as they are and the ranges of them each draws.  Mirrored
+    /// instances come last, see [`State::set_instances`]; empty ranges are left out.
+    fn windings(&self) -> impl Iterator<Item = (&Pipelines, std::ops::Range<u32>)> {
+        let mirrored = self.instances.partition_point(|i| !i.is_mirrored()) as u32;
+        let end = self.instances.len() as u32;
+        [
+            (&self.pipelines, 0..mirrored),
+            (&self.mirrored_pipelines, mirrored..end),
+        ]
+        .into_iter()
+        .filter(|(_, instances)| !instances.is_empty())
+    }
+
+    fn draw_scene<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera_bind_group: &'a wgpu::BindGroup,
+    ) -> RenderStats {
+        let mut stats = RenderStats::default();
+        // Every mesh receives the projected image, whatever material it was loaded with
+        let material = self.scene.active_material();
+        render_pass.set_bind_group(2, &self.projector_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        for (pipelines, instances) in self.windings() {
+            if let Some(wireframe) = pipelines.wireframe.as_ref().filter(|_| self.wireframe) {
+                render_pass.set_pipeline(wireframe);
+            } else if self.coverage_debug {
+                match &self.projector_set {
+                    Some(set) => {
+                        render_pass.set_pipeline(&pipelines.coverage);
+                        render_pass.set_bind_group(3, &set.bind_group, &[]);
+                    }
+                    None => render_pass.set_pipeline(&pipelines.coverage_single),
+                }
+            } else if let Some(set) = &self.projector_set {
+                render_pass.set_pipeline(&pipelines.blend);
+                render_pass.set_bind_group(3, &set.bind_group, &[]);
+            } else if let Some(flipbook) = &self.flipbook {
+                render_pass.set_pipeline(&pipelines.flipbook);
+                render_pass.set_bind_group(3, &flipbook.bind_group, &[]);
+            } else if material.premultiplied {
+                render_pass.set_pipeline(&pipelines.premultiplied);
+            } else {
+                render_pass.set_pipeline(&pipelines.fill);
+            }
+            for mesh in &self.scene.meshes {
+                render_pass.draw_mesh_instanced(
+                    mesh,
+                    material,
+                    instances.clone(),
+                    camera_bind_group,
+                );
+                stats.add_mesh(mesh, instances.len() as u32);
+            }
+        }
+        // The overlay would only darken the edges wireframe mode already draws
+        let overlay = self.wire_overlay && !self.wireframe;
+        for (pipelines, instances) in self.windings() {
+            if let Some(wire_pipeline) = pipelines.wire.as_ref().filter(|_| overlay) {
+                render_pass.set_pipeline(wire_pipeline);
+                for mesh in &self.scene.meshes {
+                    render_pass.draw_mesh_instanced(
+                        mesh,
+                        material,
+                        instances.clone(),
+                        camera_bind_group,
+                    );
+                    stats.add_mesh(mesh, instances.len() as u32);
+                }
+            }
+        }
+        render_pass.set_pipeline(&self.pipelines.billboard);
+        for billboards in &self.billboards {
+            let instances = 0..billboards.positions.len() as u32;
+            render_pass.set_vertex_buffer(1, billboards.instance_buffer.slice(..));
+            render_pass.draw_mesh_instanced(
+                &billboards.mesh,
+                &billboards.material,
+                instances.clone(),
+                camera_bind_group,
+            );
+            stats.add_mesh(&billboards.mesh, instances.end);
+        }
+        render_pass.set_pipeline(&self.pipelines.lines);
+        // Lines don't sample the image, but share the layout with its group
+        render_pass.set_bind_group(0, &material.bind_group, &[]);
+        for overlay in self
+            .grid
+            .iter()
+            .chain(&self.frustum)
+            .chain(&self.measurement)
+        {
+            render_pass.set_vertex_buffer(1, overlay.instance_buffer.slice(..));
+            render_pass.draw_line_mesh_instanced(&overlay.mesh, 0..1, camera_bind_group);
+        }
+        stats
+    }
+
+    /// Pass drawing into `view`, or into `msaa_view` resolved into `view` when multisampling
+    fn begin_render_pass<'a>(
+        &self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        msaa_view: Option<&'a wgpu::TextureView>,
+        depth_view: &'a wgpu::TextureView,
+        clear_color: wgpu::Color,
+    ) -> wgpu::RenderPass<'a> {
+        let render_pass_desc = &wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: msaa_view.unwrap_or(view),
+                resolve_target: msaa_view.map(|_| view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        };
+
+        encoder.begin_render_pass(render_pass_desc)
+    }
+
+    /// Record the main view into `view`, a `width` × `height` target, seen through
+    /// `camera_bind_group`: the letterbox bars and background, then the scene once per viewport.
+    /// When multisampling it is drawn into `msaa_view` and resolved into `view`.
+    fn record_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        msaa_view: Option<&wgpu::TextureView>,
+        depth_view: &wgpu::TextureView,
+        (width, height): (u32, u32),
+        camera_bind_group: &wgpu::BindGroup,
+    ) -> RenderStats {
+        self.face_billboards();
+        let letterbox = self
+            .letterbox_rect(width, height)
+            .filter(|_| self.viewports.is_empty());
+        if letterbox.is_some() {
+            let color = self.background_color();
+            let color = [color.r, color.g, color.b, color.a].map(|c| c as f32);
+            self.queue
+                .write_buffer(&self.background_buffer, 0, bytemuck::cast_slice(&color));
+        }
+        let clear_color = match letterbox {
+            Some(_) => self.letterbox_color,
+            None => self.background_color(),
+        };
+        let mut render_pass =
+            self.begin_render_pass(encoder, view, msaa_view, depth_view, clear_color);
+        let mut stats = RenderStats::default();
+        if let Some((x, y, width, height)) = letterbox {
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.set_pipeline(&self.pipelines.background);
+            render_pass.set_vertex_buffer(0, self.background_buffer.slice(..));
+            render_pass.draw(0..3, 0..1);
+            stats.draw_calls += 1;
+        }
+        if self.viewports.is_empty() {
+            stats += self.draw_scene(&mut render_pass, camera_bind_group);
+        }
+        for target in &self.viewports {
+            let (x, y, width, height) = target.viewport.pixel_rect(width, height);
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_scissor_rect(x, y, width, height);
+            stats += self.draw_scene(&mut render_pass, &target.bind_group);
+        }
+        stats
+    }
+
+    /// Render the main view into a new `width` × `height` texture in the pipelines' format,
+    /// without touching any window, e.g. to batch-generate projected images.  The camera takes
+    /// the texture's aspect ratio unless a target aspect is set; split views keep the window's.
+    pub fn render_to_texture(&self, width: u32, height: u32) -> texture::Texture {
+        self.render_to_texture_jittered(width, height, glam::Vec2::ZERO)
+    }
+
+    /// Render a `size` × `size` plan of the scene: looking down `-up` with an orthographic view
+    /// `extent` world units across, centered on the meshes, with the projectors' layout as a
+    /// [`cube::Schematic`] over the floor under them in place of the frustum outline.  The
+    /// camera, target aspect, split views and overlays are restored afterwards.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_schematic(&mut self, size: u32, extent: f32, up: glam::Vec3) -> texture::Texture {
+        let up = up.normalize();
+        let bounds = self
+            .scene
+            .meshes
+            .iter()
+            .map(model::Mesh::aabb)
+            .reduce(|a, b| a.merge(&b));
+        let (center, reach) = bounds.map_or((glam::Vec3::ZERO, 0.0), |aabb| {
+            (aabb.center(), up.abs().dot(aabb.extents()))
+        });
+        let projectors = match self.scene.projectors.as_slice() {
+            [] => vec![self.scene.projector],
+            projectors => projectors.to_vec(),
+        };
+        // High enough to see every mesh and projector from above
+        let height = projectors
+            .iter()
+            .map(|projector| up.dot(projector.eye - center))
+            .fold(reach, f32::max)
+            + 1.0;
+        // North, -Z, points up in the image unless `up` is along it
+        let image_up = (glam::Vec3::NEG_Z - up * -up.z)
+            .try_normalize()
+            .unwrap_or(glam::Vec3::Y);
+        let camera = camera::Camera {
+            eye: center + up * height,
+            target: center,
+            up: image_up,
+            aspect: 1.0,
+            fovy: 2.0 * (extent * 0.5 / height).atan(),
+            znear: 0.01,
+            zfar: height + reach + 1.0,
+            kind: camera::ProjectionKind::Orthographic,
+        };
+        // Just above the floor so the outlines aren't hidden in it
+        let floor = center - up * (reach - extent * 1e-3);
+        let schematic = cube::Schematic::new(
+            "schematic",
+            &projectors,
+            floor,
+            up,
+            extent * 0.02,
+            &self.device,
+        );
+        let overlay = LineOverlay {
+            mesh: schematic.into(),
+            instance_buffer: create_instance_buffer(&self.device, &[Instance::IDENTITY]),
+        };
+
+        let frustum = self.frustum.replace(overlay);
+        let viewports = std::mem::take(&mut self.viewports);
+        let scene_camera = std::mem::replace(&mut self.scene.camera, camera);
+        let target_aspect = self.target_aspect.take();
+        let target = self.render_to_texture(size, size);
+        self.frustum = frustum;
+        self.viewports = viewports;
+        self.scene.camera = scene_camera;
+        self.target_aspect = target_aspect;
+        target
+    }
+
+    /// [`Self::render_to_texture`] with the camera's image moved by `jitter` pixels, a fraction
+    /// of one to sample between pixel centers
+    fn render_to_texture_jittered(
+        &self,
+        width: u32,
+        height: u32,
+        jitter: glam::Vec2,
+    ) -> texture::Texture {
+        let (width, height) = (width.max(1), height.max(1));
+        let format = self.pipeline_sources.format;
+        let target = texture::Texture::create_render_target(
+            &self.device,
+            width,
+            height,
+            format,
+            "offscreen_target",
+        );
+        // Only the size and format matter to the attachments
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let depth_texture = texture::Texture::create_depth_texture(
+            &self.device,
+            &config,
+            self.sample_count,
+            "offscreen_depth_texture",
+        );
+        let msaa_target = create_msaa_target(&self.device, &config, self.sample_count);
+
+        let camera_bind_group = self.offscreen_camera_bind_group(width, height, jitter);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen Render Encoder"),
+            });
+        self.record_pass(
+            &mut encoder,
+            &target.view,
+            msaa_target.as_ref().map(|target| &target.view),
+            &depth_texture.view,
+            (width, height),
+            &camera_bind_group,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+        target
+    }
+
+    /// Copy `target`, e.g. from [`Self::render_to_texture`], back from the GPU as rows of RGBA8
+    /// pixels.  Blocks until the copy is done.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_pixels(&self, target: &texture::Texture) -> anyhow::Result<Vec<u8>> {
+        let bgra = match target.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => anyhow::bail!("Can't read back {format:?} pixels"),
+        };
+        let mut pixels = self.read_texels(target)?;
+        if bgra {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// Bind group of a camera buffer holding `uniform` for a single render, unlike the ones the
+    /// windows update every frame
+    fn camera_bind_group(&self, uniform: &camera::CameraUniform, label: &str) -> wgpu::BindGroup {
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(&[*uniform]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some(label),
+        })
+    }
+
+    /// The main camera for a `width` × `height` offscreen target, taking its aspect ratio
+    /// unless a target aspect is set, and its image moved by `jitter` pixels
+    fn offscreen_camera_bind_group(
+        &self,
+        width: u32,
+        height: u32,
+        jitter: glam::Vec2,
+    ) -> wgpu::BindGroup {
+        let mut camera = self.scene.camera;
+        camera.aspect = self.target_aspect.unwrap_or(width as f32 / height as f32);
+        // Clip space spans 2 across the target, `y` pointing up
+        let jitter = jitter * glam::vec2(2.0 / width as f32, -2.0 / height as f32);
+        let jitter = glam::Mat4::from_translation(jitter.extend(0.0));
+        let mut uniform = camera::CameraUniform::new();
+        uniform.set_view_proj(jitter * camera.build_view_projection_matrix(), camera.eye);
+        self.camera_bind_group(&uniform, "offscreen_camera_bind_group")
+    }
+
+    /// Render the main view into a `width` × `height` frame seen with the scene's projector,
+    /// together with the world normals, projector texture coordinates and camera distances it
+    /// is shaded from, all in one pass with a target each.  Overlays, split views, multisampling
+    /// and projector sets are left out.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_gbuffer(&self, width: u32, height: u32) -> anyhow::Result<GBuffer> {
+        let (width, height) = (width.max(1), height.max(1));
+        let format = self.pipeline_sources.format;
+        let targets = [format]
+            .into_iter()
+            .chain(GBUFFER_FORMATS)
+            .map(|format| {
+                texture::Texture::create_render_target(
+                    &self.device,
+                    width,
+                    height,
+                    format,
+                    "gbuffer_target",
+                )
+            })
+            .collect::<Vec<_>>();
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let depth_texture =
+            texture::Texture::create_depth_texture(&self.device, &config, 1, "gbuffer_depth");
+        let camera_bind_group = self.offscreen_camera_bind_group(width, height, glam::Vec2::ZERO);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("G-Buffer Encoder"),
+            });
+        {
+            let color_attachments: Vec<_> = targets
+                .iter()
+                .enumerate()
+                .map(|(i, target)| {
+                    // The data targets are cleared to 0, nothing drawn
+                    let clear = match i {
+                        0 => self.background_color(),
+                        _ => wgpu::Color::TRANSPARENT,
+                    };
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &target.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(clear),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })
+                })
+                .collect();
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("G-Buffer Pass"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_bind_group(2, &self.projector_bind_group, &[]);
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            let material = self.scene.active_material();
+            for (pipelines, instances) in self.windings() {
+                render_pass.set_pipeline(&pipelines.gbuffer);
+                for mesh in &self.scene.meshes {
+                    render_pass.draw_mesh_instanced(
+                        mesh,
+                        material,
+                        instances.clone(),
+                        &camera_bind_group,
+                    );
+                }
+            }
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let floats = |target| -> anyhow::Result<Vec<f32>> {
+            Ok(bytemuck::pod_collect_to_vec(&self.read_texels(target)?))
+        };
+        let size_error = "Read back G-buffer doesn't fill the frame";
+        Ok(GBuffer {
+            color: image::RgbaImage::from_raw(width, height, self.read_pixels(&targets[0])?)
+                .context(size_error)?,
+            normal: image::ImageBuffer::from_raw(width, height, floats(&targets[1])?)
+                .context(size_error)?,
+            uv: image::ImageBuffer::from_raw(width, height, floats(&targets[2])?)
+                .context(size_error)?,
+            depth: image::ImageBuffer::from_raw(width, height, floats(&targets[3])?)
+                .context(size_error)?,
+        })
+    }
+
+    /// Render the main view `samples` times, each moved by a different fraction of a pixel
+    /// along a Halton sequence, and average the frames in linear color into rows of RGBA8
+    /// pixels.  Smooths edges far beyond what multisampling does, for stills of a scene that
+    /// holds still: anything moving between the frames would be smeared.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_supersampled(
+        &self,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        let srgb = self.pipeline_sources.format.is_srgb();
+        let decode = |c: f64| if srgb { color::srgb_to_linear(c) } else { c };
+        let encode = |c: f64| if srgb { color::linear_to_srgb(c) } else { c };
+        let samples = samples.max(1);
+        let mut sum = vec![0.0; (width.max(1) * height.max(1) * 4) as usize];
+        for i in 0..samples {
+            // The first sample is the pixel center, like an ordinary frame
+            let jitter = match i {
+                0 => glam::Vec2::ZERO,
+                _ => glam::vec2(halton(i, 2), halton(i, 3)) - 0.5,
+            };
+            let target = self.render_to_texture_jittered(width, height, jitter);
+            let pixels = self.read_pixels(&target)?;
+            for (i, (sum, &channel)) in sum.iter_mut().zip(&pixels).enumerate() {
+                let channel = channel as f64 / 255.0;
+                // Alpha is linear either way
+                *sum += if i % 4 == 3 { channel } else { decode(channel) };
+            }
+        }
+        Ok(sum
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let [r, g, b, a] = [0, 1, 2, 3].map(|i| pixel[i] / samples as f64);
+                [encode(r), encode(g), encode(b), a]
+            })
+            .map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+            .collect())
+    }
+
+    /// Copy `target` back from the GPU as rows of texels in its own format, e.g. `f32`s for
+    /// the picking target.  Blocks until the copy is done.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_texels(&self, target: &texture::Texture) -> anyhow::Result<Vec<u8>> {
+        let texel_size = target
+            .format
+            .block_size(None)
+            .with_context(|| format!("Can't read back {:?} texels", target.format))?;
+        let (width, height) = (target.width(), target.height());
+        // Rows copied into a buffer are padded to a multiple of 256 bytes
+        let bytes_per_row = width * texel_size;
+        let padded_bytes_per_row = bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Readback"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            target.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            target.size,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+        let mut texels = Vec::with_capacity((bytes_per_row * height) as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks_exact(padded_bytes_per_row as usize)
+        {
+            texels.extend_from_slice(&row[..bytes_per_row as usize]);
+        }
+        readback.unmap();
+        Ok(texels)
+    }
+
+    /// The mesh and world space point seen at `pixel` of a `viewport` sized view of the main
+    /// camera, rendered on the GPU instead of raycast: exact to the pixel whatever the mesh,
+    /// and as cheap for dense meshes as for a cube.  The camera is zoomed into the pixel so it
+    /// fills a 1 × 1 target holding the fragment's world position and mesh id, which is read
+    /// back right away.  That blocks until the GPU has drawn it, so unlike reading back the
+    /// previous frame there's no latency, but each pick stalls for a round trip to the GPU.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pick_gpu(
+        &self,
+        pixel: glam::Vec2,
+        viewport: glam::Vec2,
+    ) -> anyhow::Result<Option<(MeshId, glam::Vec3)>> {
+        let camera = &self.scene.camera;
+        let view_proj = camera.build_view_projection_matrix();
+        // Scale clip space up by the viewport size around the pixel's center
+        let center = (pixel + 0.5) / viewport * glam::vec2(2.0, -2.0) + glam::vec2(-1.0, 1.0);
+        let zoom = glam::Mat4::from_cols(
+            glam::Vec4::X * viewport.x,
+            glam::Vec4::Y * viewport.y,
+            glam::Vec4::Z,
+            (-center * viewport).extend(0.0).extend(1.0),
+        );
+        let mut camera_uniform = camera::CameraUniform::new();
+        camera_uniform.set_view_proj(zoom * view_proj, camera.eye);
+        let camera_bind_group = self.camera_bind_group(&camera_uniform, "pick_camera_bind_group");
+
+        // One id per mesh, each at an offset the device can bind
+        let stride = self.device.limits().min_uniform_buffer_offset_alignment as usize;
+        let meshes = self.scene.meshes.len().max(1);
+        let mut ids = vec![0; stride * meshes];
+        for (id, chunk) in ids.chunks_exact_mut(stride).enumerate() {
+            chunk[..4].copy_from_slice(&(id as u32).to_ne_bytes());
+        }
+        let id_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Pick Id Buffer"),
+                contents: &ids,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let id_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.pick_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &id_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(PICK_ID_SIZE),
+                }),
+            }],
+            label: Some("pick_id_bind_group"),
+        });
+
+        let target =
+            texture::Texture::create_render_target(&self.device, 1, 1, PICK_FORMAT, "pick_target");
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: PICK_FORMAT,
+            width: 1,
+            height: 1,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let depth_texture =
+            texture::Texture::create_depth_texture(&self.device, &config, 1, "pick_depth_texture");
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Pick Encoder"),
+            });
+        {
+            // Cleared to a mesh id of 0, which `fs_pick` never writes
+            let mut render_pass = self.begin_render_pass(
+                &mut encoder,
+                &target.view,
+                None,
+                &depth_texture.view,
+                wgpu::Color::TRANSPARENT,
+            );
+            render_pass.set_bind_group(2, &self.projector_bind_group, &[]);
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            let material = self.scene.active_material();
+            for (pipelines, instances) in self.windings() {
+                render_pass.set_pipeline(&pipelines.pick);
+                for (id, mesh) in self.scene.iter_meshes() {
+                    render_pass.set_bind_group(3, &id_bind_group, &[(id * stride) as u32]);
+                    render_pass.draw_mesh_instanced(
+                        mesh,
+                        material,
+                        instances.clone(),
+                        &camera_bind_group,
+                    );
+                }
+            }
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let texel: [f32; 4] = bytemuck::pod_read_unaligned(&self.read_texels(&target)?);
+        let [x, y, z, id] = texel;
+        Ok((id > 0.0).then(|| (id as MeshId - 1, glam::vec3(x, y, z))))
+    }
+}
+
+impl State {
+    async fn new(
+        window: Window,
+        options: &SceneOptions,
+        window_options: &WindowOptions,
+    ) -> anyhow::Result<Self> {
+        // Ensure neither width nor height is 0
+        let size = match window.inner_size() {
+            PhysicalSize {
+                height: 0,
+                width: 0,
+            } => DEFAULT_WINDOW_SIZE,
+            size => size,
+        };
+        let scale_factor = window.scale_factor();
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+
+        let adapter_options = &wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: Some(&surface),
+            force_fallback_adapter: false,
+        };
+        let adapter = instance.request_adapter(adapter_options).await.unwrap();
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let sdr_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+        // A float surface takes linear, extended range values (scRGB where the platform
+        // supports it), which is exactly what `fs_main` outputs, so no transfer function is
+        // applied in the shader; the sRGB encoding of other formats is done by the hardware.
+        let hdr_format = wgpu::TextureFormat::Rgba16Float;
+        let surface_format = if !window_options.hdr {
+            sdr_format
+        } else if surface_caps.formats.contains(&hdr_format) {
+            hdr_format
+        } else {
+            warn!("Surface doesn't support {hdr_format:?}, falling back to {sdr_format:?}");
+            sdr_format
+        };
+        let alpha_mode = if window_options.transparent {
+            // Compositors disagree on which non-opaque modes they accept
+            [
+                wgpu::CompositeAlphaMode::PreMultiplied,
+                wgpu::CompositeAlphaMode::PostMultiplied,
+            ]
+            .into_iter()
+            .find(|mode| surface_caps.alpha_modes.contains(mode))
+            .unwrap_or_else(|| {
+                warn!("Surface doesn't support transparency, the window will be opaque");
+                surface_caps.alpha_modes[0]
+            })
+        } else {
+            surface_caps.alpha_modes[0]
+        };
+        // The same format without (or with) the sRGB transfer function, so views of the surface
+        // can be created in either color space
+        let alias_format = if surface_format.is_srgb() {
+            surface_format.remove_srgb_suffix()
+        } else {
+            surface_format.add_srgb_suffix()
+        };
+        let view_formats = if !window_options.linear_view_format {
+            vec![]
+        } else if alias_format != surface_format
+            && adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS)
+        {
+            vec![alias_format]
+        } else {
+            warn!("Surface view formats are not supported for {surface_format:?}");
+            vec![]
+        };
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode,
+            view_formats,
+        };
+        let aspect = config.width as f32 / config.height as f32;
+        let renderer = Renderer::new(instance, adapter, config.format, options, aspect).await?;
+        let window = WindowContext::new(&renderer, window, surface, config, CameraSource::Main);
+
+        let mut camera_controller =
+            camera::CameraController::new(0.2).with_damping(options.damping.unwrap_or(0.0));
+        if let Some(angle) = options.step_angle {
+            camera_controller = camera_controller.with_step_mode(angle.to_radians());
+        }
+        if let Some(bindings) = options.key_bindings {
+            camera_controller = camera_controller.with_bindings(bindings);
+        }
+        if let Some(zoom_mode) = options.zoom_mode {
+            camera_controller = camera_controller.with_zoom_mode(zoom_mode);
+        }
+
+        Ok(Self {
+            renderer,
+            window,
+            outputs: Vec::new(),
+            continuous: false,
+            camera_controller,
+            last_stats: RenderStats::default(),
+            frame_counter: stats::FrameCounter::new(),
+            idle_timeout: None,
+            last_input: instant::Instant::now(),
+            fps_cap: None,
+            last_frame: instant::Instant::now(),
+            scale_factor,
+            cursor_position: None,
+            cursor_delta: glam::Vec2::ZERO,
+            mouse_pressed: false,
+            modifiers: ModifiersState::empty(),
+            orbit_controller: options.orbit.then(camera::OrbitController::default),
+            timeline: None,
+            projector_animation: None,
+            measure_start: None,
+            measured: None,
+            last_update: instant::Instant::now(),
+        })
+    }
+
+    /// What is being rendered, for listing or inspecting the loaded meshes and materials
+    pub fn scene(&self) -> &Scene {
+        &self.renderer.scene
+    }
+
+    /// Loaded meshes with their ids, e.g. to list them in a UI
+    pub fn meshes(&self) -> impl Iterator<Item = (MeshId, &Mesh)> {
+        self.renderer.scene.iter_meshes()
+    }
+
+    /// Loaded materials with their indices, see [`State::cycle_material`]
+    pub fn materials(&self) -> impl Iterator<Item = (usize, &Material)> {
+        self.renderer.scene.iter_materials()
+    }
+
+    pub fn mesh_by_name(&self, name: &str) -> Option<(MeshId, &Mesh)> {
+        self.renderer.scene.mesh_by_name(name)
+    }
+
+    pub fn material_by_name(&self, name: &str) -> Option<(usize, &Material)> {
+        self.renderer.scene.material_by_name(name)
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window.window
+    }
+
+    /// Fit the scene to a fixed width / height ratio, e.g. that of the projector being captured
+    /// for, instead of stretching it to the window.  `None` fills the whole window again.
+    pub fn set_target_aspect(&mut self, aspect: Option<f32>) {
+        self.renderer.target_aspect = aspect.filter(|a| a.is_finite() && *a > 0.0);
+        self.renderer.scene.camera.aspect = self
+            .renderer
+            .target_aspect
+            .unwrap_or(self.window.config.width as f32 / self.window.config.height as f32);
+        self.window.request_redraw();
+    }
+
+    /// Color of the bars left around the scene by [`Self::set_target_aspect`], in sRGB
+    pub fn set_letterbox_color(&mut self, color: wgpu::Color) {
+        self.renderer.letterbox_color = color::srgb_color_to_linear(color);
+        self.window.request_redraw();
+    }
+
+    /// Ratio of physical to logical pixels of the main window
+    /// Physical pixels per logical pixel of the main window
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Convert a position in the main window to surface pixels
+    pub fn to_physical(&self, position: LogicalPosition<f64>) -> PhysicalPosition<f64> {
+        position.to_physical(self.scale_factor)
+    }
+
+    /// Convert a position in surface pixels to the main window's logical pixels
+    pub fn to_logical(&self, position: PhysicalPosition<f64>) -> LogicalPosition<f64> {
+        position.to_logical(self.scale_factor)
+    }
+
+    /// Cursor position in surface pixels, if the cursor is over the main window
+    pub fn cursor_physical_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.cursor_position
+    }
+
+    /// Record cursor motion.  Many `CursorMoved` events arrive per frame, so this only
+    /// accumulates; anything reacting to the cursor reads the total once per update through
+    /// [`Self::take_cursor_delta`] and requests a redraw itself.
+    fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        if let Some(last) = self.cursor_position {
+            self.cursor_delta +=
+                glam::vec2((position.x - last.x) as f32, (position.y - last.y) as f32);
+        }
+        self.cursor_position = Some(position);
+    }
+
+    /// Cursor motion accumulated since the last call
+    pub fn take_cursor_delta(&mut self) -> glam::Vec2 {
+        std::mem::take(&mut self.cursor_delta)
+    }
+
+    /// Counters from the most recently rendered frame of the main window
+    pub fn last_render_stats(&self) -> RenderStats {
+        self.last_stats
+    }
+
+    /// Render the scene once per viewport.  An empty list renders the main camera to the whole
+    /// window.
+    pub fn set_viewports(&mut self, viewports: Vec<Viewport>) {
+        self.renderer.viewports = viewports
+            .into_iter()
+            .map(|viewport| {
+                let uniform = camera::CameraUniform::new();
+                let buffer =
+                    self.renderer
+                        .device
+                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some("Viewport Camera Buffer"),
+                            contents: bytemuck::cast_slice(&[uniform]),
+                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                        });
+                let bind_group =
+                    self.renderer
+                        .device
+                        .create_bind_group(&wgpu::BindGroupDescriptor {
+                            layout: &self.renderer.camera_bind_group_layout,
+                            entries: &[wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: buffer.as_entire_binding(),
+                            }],
+                            label: Some("viewport_camera_bind_group"),
+                        });
+                ViewportTarget {
+                    viewport,
+                    uniform,
+                    buffer,
+                    bind_group,
+                }
+            })
+            .collect();
+        self.window.request_redraw();
+    }
+
+    /// Decode `bytes` as an image and project it instead of the current one.
+    pub fn set_texture_bytes(&mut self, bytes: &[u8], label: &str) -> anyhow::Result<()> {
+        let texture = texture::Texture::from_bytes(
+            &self.renderer.device,
+            &self.renderer.queue,
+            bytes,
+            label,
+            self.renderer.oversized,
+        )?;
+        self.renderer
+            .scene
+            .set_active_material(model::Material::new(
+                label,
+                texture,
+                &self.renderer.device,
+                &self.renderer.texture_bind_group_layout,
+            ));
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// Load an image file as an additional material and project it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_texture_file(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        let bytes = std::fs::read(path)?;
+        self.add_texture_bytes(&bytes, &path.to_string_lossy())
+    }
+
+    /// Decode `bytes` as an image, add it as a material and project it.  Unlike
+    /// [`Self::set_texture_bytes`] the current image can be cycled back to.
+    pub fn add_texture_bytes(&mut self, bytes: &[u8], label: &str) -> anyhow::Result<()> {
+        let texture = texture::Texture::from_bytes(
+            &self.renderer.device,
+            &self.renderer.queue,
+            bytes,
+            label,
+            self.renderer.oversized,
+        )?;
+        self.renderer.scene.active_material =
+            self.renderer.scene.add_material(model::Material::new(
+                label,
+                texture,
+                &self.renderer.device,
+                &self.renderer.texture_bind_group_layout,
+            ));
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// Blend the projected image using premultiplied alpha, see `Material::premultiplied`
+    pub fn set_premultiplied(&mut self, premultiplied: bool) {
+        self.renderer.scene.materials[self.renderer.scene.active_material].premultiplied =
+            premultiplied;
+        self.window.request_redraw();
+    }
+
+    /// Project the next loaded image
+    pub fn cycle_material(&mut self) {
+        info!("Projecting {}", self.renderer.scene.cycle_material().name);
+        self.window.request_redraw();
+    }
+
+    pub fn set_file_hovered(&mut self, hovered: bool) {
+        self.renderer.file_hovered = hovered;
+        self.window.request_redraw();
+    }
+
+    /// Re-read the projected image from disk, e.g. after it was edited.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_texture(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        let bytes = std::fs::read(path)?;
+        self.set_texture_bytes(&bytes, &path.to_string_lossy())
+    }
+
+    /// Write the main camera to a glTF file so the viewpoint can be reused in other tools.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_camera_gltf(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.renderer.scene.camera.gltf_json())?;
+        Ok(())
+    }
+
+    /// Render the main view `samples` times at `width` × `height`, moving it by fractions of a
+    /// pixel, and average them into an image with much smoother edges than multisampling
+    /// gives, e.g. 16 samples for a final screenshot.  Only for a scene that holds still, see
+    /// [`Renderer::render_supersampled`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_supersampled(
+        &self,
+        samples: u32,
+        (width, height): (u32, u32),
+    ) -> anyhow::Result<image::RgbaImage> {
+        let (width, height) = (width.max(1), height.max(1));
+        let pixels = self.renderer.render_supersampled(width, height, samples)?;
+        image::RgbaImage::from_raw(width, height, pixels)
+            .context("Averaged pixels don't fill the frame")
+    }
+
+    /// Write a top-down plan of the installation to a PNG file, for documenting or planning
+    /// where projectors go: the meshes seen from above along `-up` over a square `extent` world
+    /// units across, with each projector's position, aim and the outline of what it covers on
+    /// the floor.  See [`Renderer::render_schematic`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_schematic(
+        &mut self,
+        path: &std::path::Path,
+        extent: f32,
+        up: glam::Vec3,
+    ) -> anyhow::Result<()> {
+        let target = self.renderer.render_schematic(SCHEMATIC_SIZE, extent, up);
+        let pixels = self.renderer.read_pixels(&target)?;
+        image::RgbaImage::from_raw(SCHEMATIC_SIZE, SCHEMATIC_SIZE, pixels)
+            .context("Read back pixels don't fill the schematic")?
+            .save(path)?;
+        Ok(())
+    }
+
+    /// The main view at the window's size along with the world normals, projector texture
+    /// coordinates and camera distances behind it, e.g. for reprojecting the image offline or
+    /// checking what the projection is computed from.  See [`Renderer::render_gbuffer`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_gbuffer(&self) -> anyhow::Result<GBuffer> {
+        let (width, height) = (self.window.config.width, self.window.config.height);
+        self.renderer.render_gbuffer(width, height)
+    }
+
+    /// Render the main view at the window's size and write it to a PNG file.  The swapchain
+    /// can't be copied from, so the frame is drawn again with [`Renderer::render_to_texture`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_frame(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let (width, height) = (self.window.config.width, self.window.config.height);
+        let target = self.renderer.render_to_texture(width, height);
+        let pixels = self.renderer.read_pixels(&target)?;
+        image::RgbaImage::from_raw(width, height, pixels)
+            .context("Read back pixels don't fill the frame")?
+            .save(path)?;
+        Ok(())
+    }
+
+    /// Draw a wireframe over the shaded mesh.  Has no effect if the adapter lacks
+    /// `POLYGON_MODE_LINE`.
+    pub fn set_wire_overlay(&mut self, enabled: bool) {
+        self.renderer.wire_overlay = enabled;
+        self.window.request_redraw();
+    }
+
+    /// Switch between drawing filled triangles and only their edges, to inspect the topology of
+    /// a mesh.  Stays filled if the adapter lacks `POLYGON_MODE_LINE`.
+    pub fn toggle_wireframe(&mut self) {
+        if self.renderer.pipelines.wireframe.is_none() {
+            warn!("Adapter does not support POLYGON_MODE_LINE, staying in fill mode");
+            return;
+        }
+        self.renderer.wireframe = !self.renderer.wireframe;
+        self.window.request_redraw();
+    }
+
+    /// Color meshes by how many projectors cover them instead of projecting, to find overlaps
+    /// and gaps: black for none, blue for one, green for two and red for three or more.  Counts
+    /// the projectors added with [`Self::add_projector`], or the scene's projector without any.
+    pub fn set_coverage_debug(&mut self, enabled: bool) {
+        self.renderer.coverage_debug = enabled;
+        self.window.request_redraw();
+    }
+
+    /// Project the image from `projector` instead
+    pub fn set_projector(&mut self, projector: camera::Projector) {
+        self.renderer.set_projector(projector);
+        self.window.request_redraw();
+    }
+
+    /// Turn the projector smoothly to `rotation` over `duration` seconds while moving it to
+    /// `eye`, see [`Projector::rotation`] and [`Projector::slerp_to`]
+    pub fn animate_projector(&mut self, rotation: glam::Quat, eye: glam::Vec3, duration: f32) {
+        let start = self.renderer.scene.projector;
+        self.projector_animation = Some(camera::ProjectorAnimation::new(
+            start, rotation, eye, duration,
+        ));
+        self.window.request_redraw();
+    }
+
+    /// Move the projector's principal point by `delta`, a fraction of its image, see
+    /// [`camera::Projector::lens_shift`]
+    pub fn nudge_lens_shift(&mut self, delta: glam::Vec2) {
+        let projector = self.renderer.scene.projector;
+        let shift = projector.lens_shift + delta;
+        self.set_projector(projector.with_lens_shift(shift));
+        info!(
+            "Lens shift {:+.0}% horizontally, {:+.0}% vertically",
+            shift.x * 100.0,
+            shift.y * 100.0
+        );
+    }
+
+    /// Pause or resume the projector's animation, leaving the camera free to move
+    pub fn toggle_projector_paused(&mut self) {
+        let projector = self.renderer.scene.projector;
+        self.set_projector(camera::Projector {
+            paused: !projector.paused,
+            ..projector
+        });
+        let action = if projector.paused {
+            "resumed"
+        } else {
+            "paused"
+        };
+        info!("Projector {action}");
+    }
+
+    /// Show or hide the outline of the volume the scene's projector covers, to check what it
+    /// reaches
+    pub fn toggle_frustum(&mut self) {
+        self.renderer.frustum = match self.renderer.frustum {
+            Some(_) => None,
+            None => Some(self.renderer.frustum_outline()),
+        };
+        self.window.request_redraw();
+    }
+
+    /// World space distance from `p1` to `p2`, also drawn as a line between them, logged and
+    /// shown in the title until [`Self::clear_measurement`], e.g. to check the scale of a
+    /// projection against the real surface.  Shift+clicking two points measures between them.
+    pub fn measure(&mut self, p1: glam::Vec3, p2: glam::Vec3) -> f32 {
+        let distance = p1.distance(p2);
+        let vertices = [p1, p2].map(|point| model::LineVertex {
+            position: point.into(),
+            color: [0.0, 1.0, 1.0],
+        });
+        let device = &self.renderer.device;
+        self.renderer.measurement = Some(LineOverlay {
+            mesh: model::LineMesh::new("measurement", device, &vertices, &[0, 1]),
+            instance_buffer: create_instance_buffer(device, &[Instance::IDENTITY]),
+        });
+        self.measured = Some(distance);
+        info!("Measured {distance:.3} from {p1} to {p2}");
+        self.window.request_redraw();
+        distance
+    }
+
+    /// Remove the measurement shown and any point Shift+clicked towards the next
+    pub fn clear_measurement(&mut self) {
+        self.renderer.measurement = None;
+        self.measure_start = None;
+        self.measured = None;
+        self.window.request_redraw();
+    }
+
+    /// Show or hide a ground grid under the meshes, for a sense of their size and position
+    pub fn toggle_grid(&mut self) {
+        if self.renderer.grid.take().is_none() {
+            // Just above the lowest point so the grid isn't hidden in a floor the meshes stand on
+            let floor = self
+                .renderer
+                .scene
+                .meshes
+                .iter()
+                .map(|mesh| mesh.aabb().min.y)
+                .reduce(f32::min)
+                .unwrap_or(0.0);
+            let instance = Instance {
+                position: glam::vec3(0.0, floor + 1e-3, 0.0),
+                ..Instance::IDENTITY
+            };
+            self.renderer.grid = Some(LineOverlay {
+                mesh: cube::Grid::new_with_axes("ground_grid", 5.0, 10, &self.renderer.device)
+                    .into(),
+                instance_buffer: create_instance_buffer(&self.renderer.device, &[instance]),
+            });
+        }
+        self.window.request_redraw();
+    }
+
+    /// View the scene from `camera`, e.g. one restored with [`camera::Camera::load`].  The
+    /// aspect ratio stays that of the window.
+    pub fn set_camera(&mut self, camera: camera::Camera) {
+        self.renderer.scene.camera = camera::Camera {
+            aspect: self.renderer.scene.camera.aspect,
+            ..camera
+        };
+        self.camera_controller.reset();
+        self.window.request_redraw();
+    }
+
+    /// Point the light along `direction`, which doesn't have to be normalized
+    pub fn set_light_direction(&mut self, direction: glam::Vec3) {
+        self.renderer.set_light_direction(direction);
+        self.window.request_redraw();
+    }
+
+    /// Stamp `image` onto the surface at `position` facing `normal`, `size` world units
+    /// across, e.g. a logo placed with a Ctrl+click.  See [`Renderer::place_decal`].
+    pub fn place_decal(
+        &mut self,
+        position: glam::Vec3,
+        normal: glam::Vec3,
+        image: texture::Texture,
+        size: f32,
+    ) -> anyhow::Result<()> {
+        self.renderer.place_decal(position, normal, image, size)?;
+        info!("Placed a decal at {position}");
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// Mark `positions` with `texture` on `size` × `size` squares turned to face the camera
+    /// however it moves, e.g. to show feature points or where projectors hang.  Many are drawn
+    /// in one call as instances, see [`Renderer::add_billboards`].
+    pub fn add_billboards(
+        &mut self,
+        positions: &[glam::Vec3],
+        size: f32,
+        texture: texture::Texture,
+    ) {
+        self.renderer.add_billboards(positions, size, texture);
+        self.window.request_redraw();
+    }
+
+    /// Blend another projector with the others, see [`Renderer::add_projector`]
+    pub fn add_projector(
+        &mut self,
+        projector: camera::Projector,
+        texture: texture::Texture,
+    ) -> anyhow::Result<()> {
+        self.renderer.add_projector(projector, texture)?;
+        self.window.request_redraw();
+        Ok(())
+    }
+
+    /// Color of surfaces outside the projected image, in sRGB
+    pub fn set_base_color(&mut self, color: wgpu::Color) {
+        self.set_projector(camera::Projector {
+            base_color: color::srgb_color_to_linear(color),
+            ..self.renderer.scene.projector
+        });
+    }
+
+    /// Antialias with `count` samples per pixel, e.g. 4, rebuilding the pipelines and
+    /// attachments.  Falls back to 1, no multisampling, if the adapter can't do `count`.
+    pub fn set_sample_count(&mut self, count: u32) {
+        let renderer = &mut self.renderer;
+        let format = renderer.pipeline_sources.format;
+        let count = supported_sample_count(&renderer.adapter, &renderer.device, format, count);
+        if count == renderer.sample_count {
+            return;
+        }
+        renderer.sample_count = count;
+        let sources = &renderer.pipeline_sources;
+        renderer.pipelines = Pipelines::new(&renderer.device, sources, count, wgpu::FrontFace::Ccw);
+        renderer.mirrored_pipelines =
+            Pipelines::new(&renderer.device, sources, count, wgpu::FrontFace::Cw);
+        for window in std::iter::once(&mut self.window).chain(&mut self.outputs) {
+            window.create_attachments(&self.renderer);
+            window.request_redraw();
+        }
+    }
+
+    /// Draw every mesh once per instance, e.g. to repeat a model on a grid.  An empty list
+    /// draws nothing.  Mirrored instances are moved after the others, to be drawn with the
+    /// pipelines culling their other face.
+    pub fn set_instances(&mut self, mut instances: Vec<Instance>) {
+        instances.sort_by_key(Instance::is_mirrored);
+        // A buffer can't be empty, so there is always at least one placeholder
+        let raw = if instances.is_empty() {
+            &[Instance::IDENTITY][..]
+        } else {
+            &instances[..]
+        };
+        self.renderer.instance_buffer = create_instance_buffer(&self.renderer.device, raw);
+        self.renderer.instances = instances;
+        self.window.request_redraw();
+    }
+
+    /// Switch the main camera between perspective and orthographic projection
+    pub fn toggle_projection(&mut self) {
+        self.renderer.scene.camera.toggle_projection();
+        self.window.request_redraw();
+    }
+
+    /// Toggle a side-by-side view of the main camera and a snapshot of its current pose.
+    pub fn toggle_split_view(&mut self) {
+        let viewports = if self.renderer.viewports.is_empty() {
+            vec![
+                Viewport {
+                    rect: [0.0, 0.0, 0.5, 1.0],
+                    camera_uniform_source: CameraSource::Main,
+                },
+                Viewport {
+                    rect: [0.5, 0.0, 0.5, 1.0],
+                    camera_uniform_source: CameraSource::Fixed(self.renderer.scene.camera),
+                },
+            ]
+        } else {
+            Vec::new()
+        };
+        self.set_viewports(viewports);
+    }
+
+    /// Open `window` as an additional output showing the scene from `camera`.  A fixed camera
+    /// takes the window's aspect ratio.
+    pub fn add_output_window(
+        &mut self,
+        window: Window,
+        camera: CameraSource,
+    ) -> anyhow::Result<()> {
+        let surface = unsafe { self.renderer.instance.create_surface(&window) }?;
+        let caps = surface.get_capabilities(&self.renderer.adapter);
+        // The pipelines are built for the main surface's format
+        if !caps.formats.contains(&self.window.config.format) {
+            anyhow::bail!(
+                "Output window does not support surface format {:?}",
+                self.window.config.format
+            );
+        }
+        let size = window.inner_size();
+        let config = wgpu::SurfaceConfiguration {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: caps.present_modes[0],
+            alpha_mode: caps.alpha_modes[0],
+            ..self.window.config.clone()
+        };
+        let mut camera = camera;
+        if let CameraSource::Fixed(camera) = &mut camera {
+            camera.aspect = config.width as f32 / config.height as f32;
+        }
+        let output = WindowContext::new(&self.renderer, window, surface, config, camera);
+        output.request_redraw();
+        self.outputs.push(output);
+        Ok(())
+    }
+
+    /// Show the output window `window_id` from `camera` instead.  Returns false if it is not an
+    /// output window.
+    pub fn set_output_camera(
+        &mut self,
+        window_id: winit::window::WindowId,
+        camera: CameraSource,
+    ) -> bool {
+        let Some(output) = self.outputs.iter_mut().find(|o| o.window.id() == window_id) else {
+            return false;
+        };
+        output.camera = camera;
+        // Takes the window's aspect ratio, like in `add_output_window`
+        let size = output.size();
+        output.resize(&self.renderer, size);
+        output.request_redraw();
+        true
+    }
+
+    /// Handle an event for one of the output windows.  Returns false if `window_id` is not an
+    /// output window.
+    pub fn output_event(
+        &mut self,
+        window_id: winit::window::WindowId,
+        event: &WindowEvent,
+    ) -> bool {
+        let Some(index) = self.outputs.iter().position(|o| o.window.id() == window_id) else {
+            return false;
+        };
+        match event {
+            WindowEvent::CloseRequested => {
+                self.outputs.remove(index);
+            }
+            WindowEvent::Resized(size) => self.outputs[index].resize(&self.renderer, *size),
+            WindowEvent::ScaleFactorChanged { .. } => {
+                let output = &mut self.outputs[index];
+                output.resize(&self.renderer, output.window.inner_size());
+            }
+            WindowEvent::RedrawRequested => match self.render_output(index) {
+                Ok(_) => {}
+                Err(wgpu::SurfaceError::Lost) => {
+                    let output = &mut self.outputs[index];
+                    output.resize(&self.renderer, output.size());
+                }
+                Err(e) => warn!("Output window error: {e}"),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    fn render_output(&self, index: usize) -> Result<(), wgpu::SurfaceError> {
+        let output = &self.outputs[index];
+        let frame = output.surface.get_current_texture()?;
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder =
+            self.renderer
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Output Render Encoder"),
+                });
+        // Letterboxed and split like the main window
+        self.renderer.record_pass(
+            &mut encoder,
+            &view,
+            output.msaa_target.as_ref().map(|target| &target.view),
+            &output.depth_texture.view,
+            (output.config.width, output.config.height),
+            &output.camera_bind_group,
+        );
+
+        self.renderer
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+        frame.present();
+        Ok(())
+    }
+
+    /// Block until all submitted GPU work has completed, then close the windows before the
+    /// device they draw with is released.
+    ///
+    /// Called once the event loop has exited so that in-flight submissions are not dropped.
+    /// Readbacks like [`Renderer::read_pixels`] poll the device themselves and unmap their buffer
+    /// before dropping it, so nothing is left mapped here.
+    pub fn shutdown(self) {
+        self.renderer.device.poll(wgpu::Maintain::Wait);
+        drop(self.outputs);
+        drop(self.window);
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.renderer.scene.camera.aspect = self
+                .renderer
+                .target_aspect
+                .unwrap_or(new_size.width as f32 / new_size.height as f32);
+            self.window.resize(&self.renderer, new_size);
+        }
+    }
+
+    /// Exit once there has been no keyboard, mouse or touch input for `timeout`, e.g. for
+    /// unattended installations.  `None` never exits.
+    pub fn set_idle_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.idle_timeout = timeout;
+        self.last_input = instant::Instant::now();
+    }
+
+    /// When the idle timeout expires, if one is set
+    pub fn idle_deadline(&self) -> Option<instant::Instant> {
+        self.idle_timeout.map(|timeout| self.last_input + timeout)
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::Touch(_)
+        ) {
+            self.last_input = instant::Instant::now();
+        }
+        if let WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+            ..
+        } = event
+        {
+            if let Some(cursor) = self.cursor_physical_position() {
+                if let Some((id, hit)) = self.pick(cursor) {
+                    let cursor = self.to_logical(cursor);
+                    info!(
+                        "Clicked {} at {}, ({:.0}, {:.0}) in logical pixels",
+                        self.renderer.scene.meshes[id].name, hit.position, cursor.x, cursor.y
+                    );
+                    // Ctrl+clicking stamps the projected image there as a decal a fifth the
+                    // size of the mesh
+                    if self.modifiers.control_key() {
+                        let renderer = &self.renderer;
+                        let image = renderer
+                            .scene
+                            .active_material()
+                            .diffuse_texture
+                            .duplicate(&renderer.device, &renderer.queue);
+                        let size = renderer.scene.meshes[id].bounding_sphere.1 * 0.4;
+                        if let Err(e) = self.place_decal(hit.position, hit.normal, image, size) {
+                            warn!("Couldn't place decal: {e}");
+                        }
+                    }
+                    // Shift+clicking two points measures between them
+                    if self.modifiers.shift_key() {
+                        match self.measure_start.take() {
+                            Some(start) => {
+                                self.measure(start, hit.position);
+                            }
+                            None => self.measure_start = Some(hit.position),
+                        }
+                    }
+                }
+            }
+        }
+        match event {
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Right,
+                ..
+            } => {
+                // Motion from before the press or after the release doesn't turn the camera
+                self.mouse_pressed = state.is_pressed();
+                self.take_cursor_delta();
+            }
+            WindowEvent::CursorMoved { .. } if self.mouse_pressed => self.window.request_redraw(),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            _ => {}
+        }
+        if let Some(orbit_controller) = &mut self.orbit_controller {
+            if orbit_controller.process_events(event) {
+                self.window.request_redraw();
+                return true;
+            }
+        }
         if self.camera_controller.process_events(event) {
             self.window.request_redraw();
-            return true;
+            return true;
+        }
+        false
+    }
+
+    /// The mesh and surface point under `position` in the main window, e.g. to place something
+    /// on the projection surface.  Only the single, possibly letterboxed, view can be picked.
+    pub fn pick(&self, position: PhysicalPosition<f64>) -> Option<(MeshId, model::RayHit)> {
+        let (pixel, viewport) = self.view_pixel(position)?;
+        let (origin, dir) = self.renderer.scene.camera.screen_ray(pixel, viewport);
+        self.renderer
+            .instances
+            .iter()
+            .flat_map(|instance| {
+                // Hit the mesh in its own space, distances along the ray stay the same
+                let to_world = instance.model_matrix();
+                let to_model = to_world.inverse();
+                let (origin, dir) = (
+                    to_model.transform_point3(origin),
+                    to_model.transform_vector3(dir),
+                );
+                self.renderer
+                    .scene
+                    .iter_meshes()
+                    .filter_map(move |(id, mesh)| {
+                        let hit = mesh.raycast(origin, dir)?;
+                        let position = to_world.transform_point3(hit.position);
+                        // The inverse transpose keeps normals square to scaled surfaces
+                        let normal = (to_world.inverse().transpose())
+                            .transform_vector3(hit.normal)
+                            .normalize_or_zero();
+                        Some((
+                            id,
+                            model::RayHit {
+                                position,
+                                normal,
+                                ..hit
+                            },
+                        ))
+                    })
+            })
+            .min_by(|(_, a), (_, b)| a.t.total_cmp(&b.t))
+    }
+
+    /// Like [`Self::pick`], but reading back what the GPU drew at `position`, see
+    /// [`Renderer::pick_gpu`]: exact to the pixel for any mesh, at the cost of waiting for the
+    /// GPU.  Hits the point the surface is drawn at rather than the triangle it's in.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_gpu(&self, position: PhysicalPosition<f64>) -> Option<(MeshId, glam::Vec3)> {
+        let (pixel, viewport) = self.view_pixel(position)?;
+        self.renderer
+            .pick_gpu(pixel, viewport)
+            .map_err(|e| warn!("Couldn't pick on the GPU: {e}"))
+            .ok()
+            .flatten()
+    }
+
+    /// `position` in pixels from the top left of the single, possibly letterboxed, view and the
+    /// size of that view, `None` outside it or in split view
+    fn view_pixel(&self, position: PhysicalPosition<f64>) -> Option<(glam::Vec2, glam::Vec2)> {
+        if !self.renderer.viewports.is_empty() {
+            return None;
+        }
+        let (width, height) = (self.window.config.width, self.window.config.height);
+        let (x, y, width, height) = self
+            .renderer
+            .letterbox_rect(width, height)
+            .unwrap_or((0, 0, width, height));
+        let viewport = glam::vec2(width as f32, height as f32);
+        let pixel = glam::vec2(position.x as f32 - x as f32, position.y as f32 - y as f32);
+        if pixel.cmplt(glam::Vec2::ZERO).any() || pixel.cmpge(viewport).any() {
+            return None;
+        }
+        Some((pixel, viewport))
+    }
+
+    /// Limit the frame rate, e.g. to keep `Immediate` or `Mailbox` present modes from running
+    /// the GPU flat out.  `None` or zero removes the cap.
+    pub fn set_fps_cap(&mut self, fps: Option<u32>) {
+        self.fps_cap = fps.filter(|fps| *fps > 0);
+    }
+
+    /// Whether a redraw should render now under the fps cap.  Native builds sleep until the frame
+    /// is due; on the web, where the thread can't block, early redraws are skipped and retried
+    /// on the next animation frame.
+    fn frame_due(&mut self) -> bool {
+        if let Some(fps) = self.fps_cap {
+            let interval = std::time::Duration::from_secs_f64(1.0 / fps as f64);
+            let elapsed = self.last_frame.elapsed();
+            if elapsed < interval {
+                cfg_if::cfg_if! {
+                    if #[cfg(target_arch = "wasm32")] {
+                        self.window.request_redraw();
+                        return false;
+                    } else {
+                        std::thread::sleep(interval - elapsed);
+                    }
+                }
+            }
+        }
+        self.last_frame = instant::Instant::now();
+        true
+    }
+
+    /// Redraw every frame instead of only when something changed, e.g. for animated content
+    pub fn set_continuous_rendering(&mut self, continuous: bool) {
+        self.continuous = continuous;
+        self.window.request_redraw();
+    }
+
+    /// Play the loaded images as a flipbook lasting `duration` seconds
+    pub fn set_timeline(&mut self, duration: f32, looping: bool) {
+        self.timeline = Some(Timeline::new(duration, looping));
+        self.last_update = instant::Instant::now();
+        self.window.request_redraw();
+    }
+
+    /// Position of the timeline in seconds, if one is set, e.g. to animate the scene from the
+    /// callback of [`run_with_update`]
+    pub fn time(&self) -> Option<f32> {
+        self.timeline.as_ref().map(Timeline::time)
+    }
+
+    pub fn toggle_timeline(&mut self) {
+        if let Some(timeline) = &mut self.timeline {
+            timeline.toggle_playing();
+            // Don't count the paused time as elapsed
+            self.last_update = instant::Instant::now();
+            self.window.request_redraw();
+        }
+    }
+
+    /// Project the layers of `texture`, e.g. from [`resources::load_image_sequence`], instead
+    /// of the active material.  The timeline, if set, flips through them.
+    pub fn set_image_sequence(&mut self, texture: texture::Texture) {
+        let frame_buffer = self.renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Flipbook Frame Buffer"),
+            size: std::mem::size_of::<[u32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self
+            .renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.renderer.texture_array_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: frame_buffer.as_entire_binding(),
+                    },
+                ],
+                label: Some("flipbook_bind_group"),
+            });
+        self.renderer.flipbook = Some(Flipbook {
+            texture,
+            frame_buffer,
+            bind_group,
+        });
+        self.set_flipbook_frame(0);
+    }
+
+    /// Show layer `frame` of the image sequence, clamped to the last one
+    pub fn set_flipbook_frame(&mut self, frame: usize) {
+        if let Some(flipbook) = &self.renderer.flipbook {
+            let last = flipbook.texture.size.depth_or_array_layers - 1;
+            let frame = (frame as u32).min(last);
+            self.renderer.queue.write_buffer(
+                &flipbook.frame_buffer,
+                0,
+                bytemuck::cast_slice(&[frame, 0, 0, 0]),
+            );
+            self.window.request_redraw();
+        }
+    }
+
+    /// Number of frames the timeline flips through: the image sequence layers if one is set,
+    /// otherwise the materials
+    fn frame_count(&self) -> usize {
+        match &self.renderer.flipbook {
+            Some(flipbook) => flipbook.texture.size.depth_or_array_layers as usize,
+            None => self.renderer.scene.materials.len(),
+        }
+    }
+
+    /// Pause and show the next (`frames > 0`) or a previous image
+    pub fn step_timeline(&mut self, frames: i32) {
+        let count = self.frame_count();
+        if let Some(timeline) = &mut self.timeline {
+            timeline.step(frames, count);
+            self.window.request_redraw();
+        }
+    }
+
+    /// Move the timeline by a tenth of its length per step
+    pub fn scrub_timeline(&mut self, steps: f32) {
+        if let Some(timeline) = &mut self.timeline {
+            timeline.scrub(steps * timeline.duration / 10.0);
+            self.window.request_redraw();
         }
-        false
     }
 
     fn update(&mut self) {
-        self.camera_controller.update_camera(&mut self.camera);
-        self.camera_uniform.update_view_proj(&self.camera);
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
-        self.window.request_redraw();
+        let now = instant::Instant::now();
+        let elapsed = now - self.last_update;
+        self.last_update = now;
+        let playing = self.advance_animations(elapsed.as_secs_f32());
+
+        // Anything that changes the scene requests a redraw itself; only keep drawing while the
+        // camera is in motion (or continuous rendering is on) so an idle scene costs nothing.
+        let moving = self.camera_controller.is_moving();
+        let drag = self.take_cursor_delta();
+        if self.mouse_pressed {
+            match &mut self.orbit_controller {
+                Some(orbit_controller) => orbit_controller.process_mouse(drag),
+                None => self.camera_controller.process_mouse(drag),
+            }
+        }
+        // The keys still move the camera, so the orbit is picked up from wherever it is
+        if let Some(orbit_controller) = &mut self.orbit_controller {
+            if orbit_controller.is_moving() {
+                let mut orbit = camera::OrbitCamera::from_camera(&self.renderer.scene.camera);
+                orbit_controller.update(&mut orbit, self.renderer.scene.camera.znear);
+                orbit.apply(&mut self.renderer.scene.camera);
+            }
+        }
+        self.camera_controller
+            .update_camera(&mut self.renderer.scene.camera, elapsed);
+        self.window.write_camera(&self.renderer);
+        for output in &mut self.outputs {
+            output.write_camera(&self.renderer);
+        }
+        // Taken out while the renderer fills in their uniforms
+        let mut viewports = std::mem::take(&mut self.renderer.viewports);
+        for target in &mut viewports {
+            let (_, _, width, height) = target
+                .viewport
+                .pixel_rect(self.window.config.width, self.window.config.height);
+            self.renderer.update_camera_uniform(
+                &target.viewport.camera_uniform_source,
+                Some(width as f32 / height as f32),
+                &mut target.uniform,
+            );
+            self.renderer.queue.write_buffer(
+                &target.buffer,
+                0,
+                bytemuck::cast_slice(&[target.uniform]),
+            );
+        }
+        self.renderer.viewports = viewports;
+        if moving || playing || self.continuous {
+            self.window.request_redraw();
+        }
+    }
+
+    /// Render the main view at the window's size after moving the timeline and the projector
+    /// animation on by exactly `dt` seconds, for pixel-diff tests against reference images.
+    /// Unlike drawing a frame it doesn't read the clock or the camera controls, so the same
+    /// scene and `dt` give the same pixels every run on a given adapter.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_once_deterministic(&mut self, dt: f32) -> anyhow::Result<image::RgbaImage> {
+        self.advance_animations(dt);
+        let (width, height) = (self.window.config.width, self.window.config.height);
+        let target = self.renderer.render_to_texture(width, height);
+        let pixels = self.renderer.read_pixels(&target)?;
+        image::RgbaImage::from_raw(width, height, pixels)
+            .context("Read back pixels don't fill the frame")
+    }
+
+    /// Move the timeline and the projector animation on by `dt` seconds, returning whether
+    /// either is still playing
+    fn advance_animations(&mut self, dt: f32) -> bool {
+        let mut playing = false;
+        let count = self.frame_count();
+        if let Some(timeline) = &mut self.timeline {
+            timeline.advance(dt);
+            playing = timeline.playing;
+            let frame = timeline.frame(count);
+            if self.renderer.flipbook.is_some() {
+                self.set_flipbook_frame(frame);
+            } else {
+                self.renderer.scene.active_material = frame;
+            }
+        }
+        let projector = self.renderer.scene.projector;
+        if let Some(animation) = self
+            .projector_animation
+            .as_mut()
+            .filter(|_| !projector.paused)
+        {
+            // Only the pose is animated, anything else set meanwhile stays
+            let pose = animation.advance(dt);
+            playing = true;
+            if animation.is_finished() {
+                self.projector_animation = None;
+            }
+            self.renderer.set_projector(camera::Projector {
+                eye: pose.eye,
+                target: pose.target,
+                up: pose.up,
+                ..projector
+            });
+        }
+        playing
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let output = self.window.surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-        let render_pass_desc = &wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        };
-
-        let mut render_pass = encoder.begin_render_pass(render_pass_desc);
-        render_pass.set_pipeline(&self.pipeline);
-        //self.meshes
-        //    .iter()
-        //    .take(2)
-        //    .for_each(|m| render_pass.draw_mesh(m, &self.materials[0], &self.camera_bind_group));
-        render_pass.draw_mesh(&self.meshes[1], &self.materials[0], &self.camera_bind_group);
-        drop(render_pass);
+        let mut encoder =
+            self.renderer
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        self.last_stats = self.renderer.record_pass(
+            &mut encoder,
+            &view,
+            self.window.msaa_target.as_ref().map(|target| &target.view),
+            &self.window.depth_texture.view,
+            (self.window.config.width, self.window.config.height),
+            &self.window.camera_bind_group,
+        );
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.renderer
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+        // Let finished work be reclaimed and map callbacks fire without blocking; some WebGL
+        // backends otherwise accumulate resources until the context is lost
+        self.renderer.device.poll(wgpu::Maintain::Poll);
         output.present();
 
+        // Keep the output windows in sync with the main view
+        for output in &self.outputs {
+            output.window.request_redraw();
+        }
+
+        if let Some(fps) = self.frame_counter.tick() {
+            let mut title = format!("Learn WGPU | {fps:.0} fps | {}", self.last_stats);
+            if let Some(distance) = self.measured {
+                title += &format!(" | measured {distance:.3}");
+            }
+            self.window.window.set_title(&title);
+            log::debug!("{fps:.1} fps, {}", self.last_stats);
+        }
+
         Ok(())
     }
 }
 
+/// How the event loop schedules frames.  Can be switched at runtime with `C`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Redraw as fast as possible, polling for events between frames
+    Continuous,
+    /// Sleep until input or a change to the scene requests a redraw, saving power
+    #[default]
+    OnDemand,
+}
+
+impl RenderMode {
+    fn control_flow(self) -> ControlFlow {
+        match self {
+            RenderMode::Continuous => ControlFlow::Poll,
+            RenderMode::OnDemand => ControlFlow::Wait,
+        }
+    }
+}
+
+/// Window creation options for [`run_with_options`].
+pub struct WindowOptions {
+    pub resizable: bool,
+    /// Start in borderless fullscreen
+    pub fullscreen: bool,
+    /// Index into the event loop's available monitors used for fullscreen.  `None` uses the
+    /// monitor the window is currently on.
+    pub monitor: Option<usize>,
+    pub render_mode: RenderMode,
+    /// Show what's behind the window wherever the scene is transparent, e.g. for overlays.  The
+    /// clear color's alpha then matters; it falls back to opaque where unsupported.
+    pub transparent: bool,
+    /// Allow views of the window surface in the linear counterpart of its sRGB format
+    pub linear_view_format: bool,
+    /// Render to a 16-bit float surface where available, so displays that support it can show
+    /// values outside the sRGB range
+    pub hdr: bool,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            resizable: true,
+            fullscreen: false,
+            monitor: None,
+            render_mode: RenderMode::default(),
+            transparent: false,
+            linear_view_format: false,
+            hdr: false,
+        }
+    }
+}
+
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub async fn run() {
+    run_with_options(WindowOptions::default(), SceneOptions::from_env()).await;
+}
+
+pub async fn run_with_options(options: WindowOptions, scene_options: SceneOptions) {
+    run_with_update(options, scene_options, |_| {}).await;
+}
+
+/// Like [`run_with_options`], calling `update` before every frame is drawn, after the camera
+/// and timeline have moved, to animate or inspect the scene through [`State`]
+pub async fn run_with_update(
+    options: WindowOptions,
+    scene_options: SceneOptions,
+    mut update: impl FnMut(&mut State),
+) {
     cfg_if::cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -422,11 +3672,27 @@ pub async fn run() {
         }
     }
 
-    let event_loop = EventLoop::new().unwrap();
+    let event_loop = EventLoopBuilder::<UserEvent>::with_user_event()
+        .build()
+        .unwrap();
+    event_loop.set_control_flow(options.render_mode.control_flow());
+
+    // On wasm there are no enumerable monitors; `Borderless(None)` falls back to the canvas
+    // fullscreen API.
+    let monitor: Option<MonitorHandle> = options
+        .monitor
+        .and_then(|i| event_loop.available_monitors().nth(i));
 
     let window = WindowBuilder::new()
         .with_title("Learn WGPU")
         .with_inner_size(DEFAULT_WINDOW_SIZE)
+        .with_resizable(options.resizable)
+        .with_transparent(options.transparent)
+        .with_fullscreen(
+            options
+                .fullscreen
+                .then(|| Fullscreen::Borderless(monitor.clone())),
+        )
         .build(&event_loop)
         .unwrap();
 
@@ -444,12 +3710,75 @@ pub async fn run() {
                 Some(())
             })
             .expect("Couldn't append canvas to document body.");
+        if let Some(document) = web_sys::window().and_then(|win| win.document()) {
+            web::watch_file_input(&document, event_loop.create_proxy());
+        }
+        if let Some(canvas) = window.canvas() {
+            web::watch_dropped_files(&canvas, event_loop.create_proxy());
+        }
+    }
+
+    let mut state = match State::new(window, &scene_options, &options).await {
+        Ok(state) => state,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
+    };
+    state.set_continuous_rendering(options.render_mode == RenderMode::Continuous);
+    state.set_target_aspect(scene_options.aspect);
+    state.set_idle_timeout(scene_options.idle_timeout);
+    state.set_fps_cap(scene_options.fps_cap);
+    state.set_premultiplied(scene_options.premultiplied);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(dir) = &scene_options.image_sequence {
+        match resources::load_image_sequence(dir, &state.renderer.device, &state.renderer.queue) {
+            Ok(texture) => state.set_image_sequence(texture),
+            Err(e) => warn!("Couldn't load image sequence {dir}: {e}"),
+        }
+    }
+    if let Some(duration) = scene_options.flipbook {
+        state.set_timeline(duration, true);
+    }
+    if let Some(color) = scene_options.letterbox_color {
+        state.set_letterbox_color(color);
+    }
+    if let Some(color) = scene_options.base_color {
+        state.set_base_color(color);
+    }
+    if let Some(count) = scene_options.msaa {
+        state.set_sample_count(count);
+    }
+    if let Some(n) = scene_options.grid {
+        state.set_instances(grid_instances(&state.renderer.scene, n));
+    }
+    for eye in &scene_options.projectors {
+        let projector = camera::Projector {
+            eye: *eye,
+            ..state.renderer.scene.projector
+        };
+        let added = load_image(
+            &scene_options,
+            &state.renderer.device,
+            &state.renderer.queue,
+            state.renderer.oversized,
+        )
+        .await
+        .and_then(|texture| state.add_projector(projector, texture));
+        if let Err(e) = added {
+            warn!("Couldn't add projector at {eye}: {e}");
+        }
     }
 
-    let mut state = State::new(window).await;
+    #[cfg(feature = "hot-reload")]
+    let _watcher = scene_options.image.as_ref().and_then(|path| {
+        hot_reload::watch_image(path.as_ref(), event_loop.create_proxy())
+            .map_err(|e| warn!("Couldn't watch {path}: {e}"))
+            .ok()
+    });
 
     event_loop
-        .run(move |event, elwt| match event {
+        .run(|event, elwt| match event {
             Event::WindowEvent {
                 ref event,
                 window_id,
@@ -457,6 +3786,21 @@ pub async fn run() {
                 if !state.input(event) {
                     match &event {
                         WindowEvent::CloseRequested => elwt.exit(),
+                        // Key releases are not delivered to unfocused windows
+                        WindowEvent::Focused(false) => {
+                            state.camera_controller.reset();
+                            state.mouse_pressed = false;
+                        }
+                        WindowEvent::HoveredFile(_) => state.set_file_hovered(true),
+                        WindowEvent::HoveredFileCancelled => state.set_file_hovered(false),
+                        // One event is sent per file when several are dropped at once
+                        #[cfg(not(target_arch = "wasm32"))]
+                        WindowEvent::DroppedFile(path) => {
+                            state.set_file_hovered(false);
+                            if let Err(e) = state.add_texture_file(path) {
+                                warn!("Couldn't load {}: {e}", path.display());
+                            }
+                        }
                         WindowEvent::KeyboardInput { event, .. } => {
                             if event.state.is_pressed() {
                                 match event.physical_key {
@@ -466,31 +3810,817 @@ pub async fn run() {
                                     PhysicalKey::Code(KeyCode::Escape) => {
                                         elwt.exit();
                                     }
+                                    PhysicalKey::Code(KeyCode::KeyO) => {
+                                        state.set_wire_overlay(!state.renderer.wire_overlay);
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyZ) => {
+                                        state.toggle_wireframe();
+                                    }
+                                    PhysicalKey::Code(KeyCode::Delete) => {
+                                        state.clear_measurement();
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyU) => {
+                                        state.set_coverage_debug(!state.renderer.coverage_debug);
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyB) => {
+                                        state.toggle_frustum();
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyH) => {
+                                        state.toggle_grid();
+                                    }
+                                    // Light the scene from the current viewpoint
+                                    PhysicalKey::Code(KeyCode::KeyL) => {
+                                        let camera = &state.renderer.scene.camera;
+                                        state.set_light_direction(camera.target - camera.eye);
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyC) => {
+                                        let mode = if state.continuous {
+                                            RenderMode::OnDemand
+                                        } else {
+                                            RenderMode::Continuous
+                                        };
+                                        elwt.set_control_flow(mode.control_flow());
+                                        state.set_continuous_rendering(
+                                            mode == RenderMode::Continuous,
+                                        );
+                                    }
+                                    // Open a window seeing through the projector, to show on
+                                    // the projector's display
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::KeyN) => {
+                                        let window = WindowBuilder::new()
+                                            .with_title("Output")
+                                            .with_inner_size(DEFAULT_WINDOW_SIZE)
+                                            .build(elwt)
+                                            .map_err(anyhow::Error::from)
+                                            .and_then(|window| {
+                                                state.add_output_window(
+                                                    window,
+                                                    CameraSource::Projector,
+                                                )
+                                            });
+                                        if let Err(e) = window {
+                                            warn!("Couldn't open output window: {e}");
+                                        }
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyM) => {
+                                        state.cycle_material();
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyV) => {
+                                        state.toggle_split_view();
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyP) => {
+                                        state.toggle_projection();
+                                    }
+                                    PhysicalKey::Code(KeyCode::KeyT) => {
+                                        state.toggle_projector_paused();
+                                    }
+                                    PhysicalKey::Code(KeyCode::BracketLeft) => {
+                                        let projector = state.renderer.scene.projector;
+                                        state.set_projector(projector.with_flip(
+                                            !projector.flip_horizontal,
+                                            projector.flip_vertical,
+                                        ));
+                                    }
+                                    PhysicalKey::Code(KeyCode::BracketRight) => {
+                                        let projector = state.renderer.scene.projector;
+                                        state.set_projector(projector.with_flip(
+                                            projector.flip_horizontal,
+                                            !projector.flip_vertical,
+                                        ));
+                                    }
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::KeyG) => {
+                                        let path = std::path::Path::new("camera.gltf");
+                                        match state.export_camera_gltf(path) {
+                                            Ok(()) => {
+                                                info!("Exported camera to {}", path.display())
+                                            }
+                                            Err(e) => warn!("Couldn't export camera: {e}"),
+                                        }
+                                    }
+                                    // Shift+F12 saves a 16 times supersampled still instead
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::F12)
+                                        if state.modifiers.shift_key() =>
+                                    {
+                                        let path = std::path::Path::new("still.png");
+                                        let config = &state.window.config;
+                                        let saved = state
+                                            .capture_supersampled(16, (config.width, config.height))
+                                            .and_then(|image| Ok(image.save(path)?));
+                                        match saved {
+                                            Ok(()) => info!("Saved still to {}", path.display()),
+                                            Err(e) => warn!("Couldn't save still: {e}"),
+                                        }
+                                    }
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::F12) => {
+                                        let path = std::path::Path::new("frame.png");
+                                        match state.save_frame(path) {
+                                            Ok(()) => info!("Saved frame to {}", path.display()),
+                                            Err(e) => warn!("Couldn't save frame: {e}"),
+                                        }
+                                    }
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::KeyK) => {
+                                        let path = std::path::Path::new("camera.ron");
+                                        match state.renderer.scene.camera.save(path) {
+                                            Ok(()) => info!("Saved camera to {}", path.display()),
+                                            Err(e) => warn!("Couldn't save camera: {e}"),
+                                        }
+                                    }
+                                    // L already points the light
+                                    #[cfg(not(target_arch = "wasm32"))]
+                                    PhysicalKey::Code(KeyCode::KeyJ) => {
+                                        let path = std::path::Path::new("camera.ron");
+                                        match camera::Camera::load(path) {
+                                            Ok(camera) => state.set_camera(camera),
+                                            Err(e) => warn!("Couldn't load camera: {e}"),
+                                        }
+                                    }
+                                    PhysicalKey::Code(KeyCode::Space) => {
+                                        state.toggle_timeline();
+                                    }
+                                    PhysicalKey::Code(KeyCode::Period) => {
+                                        state.step_timeline(1);
+                                    }
+                                    PhysicalKey::Code(KeyCode::Comma) => {
+                                        state.step_timeline(-1);
+                                    }
+                                    // Shift nudges the lens shift by 1% of the image
+                                    PhysicalKey::Code(
+                                        key @ (KeyCode::ArrowRight
+                                        | KeyCode::ArrowLeft
+                                        | KeyCode::ArrowUp
+                                        | KeyCode::ArrowDown),
+                                    ) if state.modifiers.shift_key() => {
+                                        let delta = match key {
+                                            KeyCode::ArrowRight => glam::Vec2::X,
+                                            KeyCode::ArrowLeft => glam::Vec2::NEG_X,
+                                            KeyCode::ArrowUp => glam::Vec2::NEG_Y,
+                                            _ => glam::Vec2::Y,
+                                        };
+                                        state.nudge_lens_shift(delta * 0.01);
+                                    }
+                                    PhysicalKey::Code(KeyCode::ArrowRight) => {
+                                        state.scrub_timeline(1.0);
+                                    }
+                                    PhysicalKey::Code(KeyCode::ArrowLeft) => {
+                                        state.scrub_timeline(-1.0);
+                                    }
+                                    PhysicalKey::Code(KeyCode::F11) => {
+                                        let window = state.window();
+                                        let fullscreen = match window.fullscreen() {
+                                            Some(_) => None,
+                                            None => Some(Fullscreen::Borderless(
+                                                monitor.clone().or(window.current_monitor()),
+                                            )),
+                                        };
+                                        window.set_fullscreen(fullscreen);
+                                    }
                                     _ => {}
                                 }
                             }
                         }
+                        WindowEvent::RedrawRequested if !state.frame_due() => {}
                         WindowEvent::RedrawRequested => {
                             state.update();
+                            update(&mut state);
                             match state.render() {
                                 Ok(_) => {}
-                                Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                                Err(wgpu::SurfaceError::Lost) => state.resize(state.window.size()),
                                 Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                                Err(e) => eprintln!("Some unhandled error {:?}", e),
+                                Err(e) => error!("Unhandled surface error: {e}"),
                             }
                         }
                         WindowEvent::Resized(physical_size) => {
                             info!("Resize: {:?}", physical_size);
                             state.resize(*physical_size);
                         }
-                        WindowEvent::ScaleFactorChanged { .. } => {
+                        WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                            state.scale_factor = *scale_factor;
                             state.resize(state.window().inner_size());
                         }
+                        WindowEvent::CursorMoved { position, .. } => {
+                            state.cursor_moved(*position);
+                        }
+                        WindowEvent::CursorLeft { .. } => state.cursor_position = None,
                         _ => {}
                     }
                 }
             }
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } => {
+                state.output_event(window_id, event);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Event::UserEvent(UserEvent::ImageChanged(path)) => {
+                if let Err(e) = state.reload_texture(&path) {
+                    warn!("Couldn't reload {}: {e}", path.display());
+                }
+            }
+            Event::UserEvent(UserEvent::ImageLoaded { name, bytes }) => {
+                if let Err(e) = state.set_texture_bytes(&bytes, &name) {
+                    warn!("Couldn't load {name}: {e}");
+                }
+            }
+            Event::UserEvent(UserEvent::ImageDropped { name, bytes }) => {
+                state.set_file_hovered(false);
+                if let Err(e) = state.add_texture_bytes(&bytes, &name) {
+                    warn!("Couldn't load {name}: {e}");
+                }
+            }
+            Event::UserEvent(UserEvent::FileHovered(hovered)) => state.set_file_hovered(hovered),
+            Event::AboutToWait => {
+                if let Some(deadline) = state.idle_deadline() {
+                    if instant::Instant::now() >= deadline {
+                        info!("No input before the idle timeout, exiting");
+                        elwt.exit();
+                    } else if !state.continuous {
+                        // Continuous rendering polls anyway.  winit's web event loop uses its
+                        // own clock, so there the timeout is only checked as events arrive.
+                        #[cfg(not(target_arch = "wasm32"))]
+                        elwt.set_control_flow(ControlFlow::WaitUntil(deadline));
+                    }
+                }
+            }
             _ => {}
         })
         .unwrap();
+    state.shutdown();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Default adapter.  Tests that need one are ignored by default, run them with
+    /// `cargo test -- --ignored` on a machine with a GPU or a software rasterizer.
+    fn adapter() -> (wgpu::Instance, wgpu::Adapter) {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .expect("No adapter available");
+        (instance, adapter)
+    }
+
+    /// Renderer of the scene given by `options` drawing into square sRGB textures
+    fn renderer(options: &SceneOptions) -> Renderer {
+        let (instance, adapter) = adapter();
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let renderer = Renderer::new(instance, adapter, format, options, 1.0);
+        pollster::block_on(renderer).expect("Renderer for the default scene")
+    }
+
+    /// With nothing to draw, a frame rendered offscreen and read back is the clear color
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn offscreen_frame_is_clear_color() {
+        let options = SceneOptions {
+            clear_color: Some(wgpu::Color {
+                r: 0.2,
+                g: 0.4,
+                b: 0.6,
+                a: 1.0,
+            }),
+            ..Default::default()
+        };
+        let mut renderer = renderer(&options);
+        renderer.instances.clear();
+        let target = renderer.render_to_texture(8, 8);
+        let pixels = renderer.read_pixels(&target).expect("Read back the frame");
+        let pixel = &pixels[..4];
+        // The sRGB target encodes the linear clear color back to what was given
+        let expected = [51, 102, 153, 255];
+        let close = pixel.iter().zip(expected).all(|(a, b)| a.abs_diff(b) <= 1);
+        assert!(close, "Pixel {pixel:?} instead of {expected:?}");
+    }
+
+    /// Linear brightness of the red channel at the center of a frame with each projector in
+    /// `projectors` showing a solid gray image of the given sRGB value
+    fn center_with_projectors(
+        renderer: &mut Renderer,
+        projectors: &[(camera::Projector, u8)],
+    ) -> f64 {
+        let projectors: Vec<_> = projectors
+            .iter()
+            .map(|&(projector, value)| (projector, image::Rgba([value, value, value, 255])))
+            .collect();
+        center_with_images(renderer, &projectors)
+    }
+
+    /// Like [`center_with_projectors`], with each projector showing a solid `pixel`
+    fn center_with_images(
+        renderer: &mut Renderer,
+        projectors: &[(camera::Projector, image::Rgba<u8>)],
+    ) -> f64 {
+        renderer.projector_set = None;
+        renderer.scene.projectors.clear();
+        for &(projector, pixel) in projectors {
+            let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, pixel));
+            let texture = texture::Texture::from_image(
+                &renderer.device,
+                &renderer.queue,
+                &image,
+                Some("solid_gray"),
+            )
+            .expect("Solid gray texture");
+            renderer
+                .add_projector(projector, texture)
+                .expect("Add projector");
+        }
+        color::srgb_to_linear(center_pixel(renderer)[0] as f64 / 255.0)
+    }
+
+    /// sRGB pixel at the center of a 9 × 9 frame
+    fn center_pixel(renderer: &Renderer) -> [u8; 4] {
+        let target = renderer.render_to_texture(9, 9);
+        let pixels = renderer.read_pixels(&target).expect("Read back the frame");
+        let center = (4 * 9 + 4) * 4;
+        pixels[center..center + 4].try_into().unwrap()
+    }
+
+    /// Where two projectors overlap the frame shows the average of their images, so it is
+    /// never brighter than the brighter projector alone
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn overlapping_projectors_are_averaged() {
+        let mut renderer = renderer(&SceneOptions::default());
+        // Where the view's center ray meets the test plane
+        let target = glam::vec3(1.0, -1.0, -1.0);
+        let [bright, dim] =
+            [glam::vec3(-6.0, 6.0, 6.0), glam::vec3(6.0, 6.0, -6.0)].map(|eye| camera::Projector {
+                eye,
+                target,
+                ..renderer.scene.projector
+            });
+        let bright_only = center_with_projectors(&mut renderer, &[(bright, 220)]);
+        let dim_only = center_with_projectors(&mut renderer, &[(dim, 60)]);
+        let both = center_with_projectors(&mut renderer, &[(bright, 220), (dim, 60)]);
+        let average = (bright_only + dim_only) / 2.0;
+        assert!(
+            (both - average).abs() < 0.01,
+            "Overlap is {both} instead of the average {average}"
+        );
+        assert!(
+            both <= bright_only.max(dim_only),
+            "Overlap {both} is brighter than one projector, {bright_only}"
+        );
+    }
+
+    /// A surface facing the light renders brighter than one lit only by the ambient light, by
+    /// the ratio `Light::intensity` gives
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn lit_surface_is_brighter() {
+        let mut renderer = renderer(&SceneOptions::default());
+        // Projects along the view's center ray, like the camera it starts out at
+        let projector = renderer.scene.projector;
+        // The test plane faces up
+        renderer.set_light_direction(glam::Vec3::NEG_Y);
+        let lit = center_with_projectors(&mut renderer, &[(projector, 200)]);
+        renderer.set_light_direction(glam::Vec3::Y);
+        let unlit = center_with_projectors(&mut renderer, &[(projector, 200)]);
+        let ambient = renderer.scene.light.ambient as f64;
+        assert!(lit > unlit, "Lit {lit} is not brighter than unlit {unlit}");
+        assert!(
+            (unlit - lit * ambient).abs() < 0.01,
+            "Unlit {unlit} is not {ambient} of lit {lit}"
+        );
+    }
+
+    /// A fisheye projector aimed at the frame's center lights it like a perspective one, and
+    /// leaves it at the base color once the center is past half its field of view
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn fisheye_projector_covers_center() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let projector = renderer.scene.projector;
+        let perspective = center_with_projectors(&mut renderer, &[(projector, 200)]);
+        let fisheye = camera::Projector {
+            fovy: 200f32.to_radians(),
+            fisheye: Some(camera::Fisheye::Equidistant),
+            ..projector
+        };
+        let covered = center_with_projectors(&mut renderer, &[(fisheye, 200)]);
+        assert!(
+            (covered - perspective).abs() < 0.01,
+            "Fisheye center is {covered} instead of {perspective}"
+        );
+        let looking_up = camera::Projector {
+            target: fisheye.eye + glam::Vec3::Y,
+            up: glam::Vec3::Z,
+            fovy: 60f32.to_radians(),
+            ..fisheye
+        };
+        let base = center_with_projectors(&mut renderer, &[(looking_up, 200)]);
+        assert!(
+            base < covered,
+            "Center past the field of view is {base}, as bright as when covered"
+        );
+    }
+
+    /// With `alpha_as_mask` a transparent image leaves the base color and an opaque one covers
+    /// it, while without it the alpha doesn't change the color
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn alpha_masks_projected_image() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let projector = renderer.scene.projector;
+        let clear = image::Rgba([200, 200, 200, 0]);
+        let opaque = image::Rgba([200, 200, 200, 255]);
+        let unmasked = center_with_images(&mut renderer, &[(projector, clear)]);
+        let covered = center_with_images(&mut renderer, &[(projector.alpha_as_mask(true), opaque)]);
+        let base = center_with_images(&mut renderer, &[(projector.alpha_as_mask(true), clear)]);
+        assert!(
+            (covered - unmasked).abs() < 0.01,
+            "Opaque mask is {covered} instead of the image's {unmasked}"
+        );
+        let expected = projector.base_color.r * covered / color::srgb_to_linear(200.0 / 255.0);
+        assert!(
+            (base - expected).abs() < 0.01,
+            "Transparent mask is {base} instead of the base color's {expected}"
+        );
+    }
+
+    /// A mask polygon around the center of the image keeps it projected there, one beside the
+    /// center leaves it at the base color
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn mask_polygon_clips_center() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let projector = renderer.scene.projector;
+        let square =
+            |x: f32| [(x, 0.4), (x + 0.2, 0.4), (x + 0.2, 0.6), (x, 0.6)].map(glam::Vec2::from);
+        let unmasked = center_with_projectors(&mut renderer, &[(projector, 200)]);
+        let around = projector.with_mask_polygon(&square(0.4));
+        let covered = center_with_projectors(&mut renderer, &[(around, 200)]);
+        let beside = projector.with_mask_polygon(&square(0.7));
+        let base = center_with_projectors(&mut renderer, &[(beside, 200)]);
+        assert!(
+            (covered - unmasked).abs() < 0.01,
+            "Center inside the mask is {covered} instead of {unmasked}"
+        );
+        assert!(
+            base < covered,
+            "Center outside the mask is {base}, still projected"
+        );
+    }
+
+    /// The coverage view is blue where one projector covers the center, green where two
+    /// overlap and black once neither does, with or without a projector set
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn coverage_counts_projectors() {
+        let mut renderer = renderer(&SceneOptions::default());
+        renderer.coverage_debug = true;
+        let projector = renderer.scene.projector;
+        let away = camera::Projector {
+            target: projector.eye + glam::Vec3::Y,
+            up: glam::Vec3::Z,
+            ..projector
+        };
+        assert_eq!(
+            center_pixel(&renderer),
+            [0, 0, 255, 255],
+            "Scene projector alone"
+        );
+        for (projectors, expected) in [
+            ([(projector, 200)].as_slice(), [0, 0, 255, 255]),
+            (&[(projector, 200), (projector, 200)], [0, 255, 0, 255]),
+            (&[(away, 200), (away, 200)], [0, 0, 0, 255]),
+        ] {
+            center_with_projectors(&mut renderer, projectors);
+            let count = projectors.len();
+            assert_eq!(
+                center_pixel(&renderer),
+                expected,
+                "Center with {count} projectors"
+            );
+        }
+    }
+
+    /// Picking on the GPU finds the same mesh and, up to depth precision, the same point as
+    /// raycasting on the CPU, and nothing where no mesh is drawn
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn gpu_pick_matches_raycast() {
+        let mut renderer = renderer(&SceneOptions::default());
+        renderer
+            .scene
+            .add_mesh(cube::Cube::new_with_normals("pick_cube", &renderer.device).into());
+        let viewport = glam::vec2(64.0, 64.0);
+        for pixel in [
+            glam::vec2(32.0, 32.0),
+            glam::vec2(20.0, 40.0),
+            glam::vec2(45.0, 28.0),
+        ] {
+            let (origin, dir) = renderer.scene.camera.screen_ray(pixel + 0.5, viewport);
+            let expected = renderer
+                .scene
+                .iter_meshes()
+                .filter_map(|(id, mesh)| Some((id, mesh.raycast(origin, dir)?)))
+                .min_by(|(_, a), (_, b)| a.t.total_cmp(&b.t))
+                .expect("Raycast hits a mesh");
+            let (id, position) = renderer
+                .pick_gpu(pixel, viewport)
+                .expect("Pick on the GPU")
+                .expect("GPU pick hits a mesh");
+            assert_eq!(id, expected.0, "Mesh at {pixel}");
+            assert!(
+                position.abs_diff_eq(expected.1.position, 1e-2),
+                "Picked {position} at {pixel} instead of {}",
+                expected.1.position
+            );
+        }
+        // Straight up from the camera, over the scene
+        renderer.scene.camera.target = renderer.scene.camera.eye + glam::Vec3::Y;
+        renderer.scene.camera.up = glam::Vec3::Z;
+        let miss = renderer
+            .pick_gpu(glam::vec2(32.0, 32.0), viewport)
+            .expect("Pick on the GPU");
+        assert!(miss.is_none(), "Picked {miss:?} in empty space");
+    }
+
+    /// Rendering the same scene offscreen twice gives the same bytes, which
+    /// `State::render_once_deterministic` relies on for comparisons with reference images
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn offscreen_render_is_deterministic() {
+        let renderer = renderer(&SceneOptions::default());
+        let [first, second] = [(); 2].map(|()| {
+            let target = renderer.render_to_texture(32, 32);
+            renderer.read_pixels(&target).expect("Read back the frame")
+        });
+        assert!(first == second, "Two renders of the same scene differ");
+    }
+
+    /// A 16 times supersampled frame softens the edges of a single sampled one with in-between
+    /// shades, and a single sample is the plain frame
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn supersampling_smooths_edges() {
+        let renderer = renderer(&SceneOptions::default());
+        let target = renderer.render_to_texture(32, 32);
+        let plain = renderer.read_pixels(&target).expect("Read back the frame");
+        let single = renderer
+            .render_supersampled(32, 32, 1)
+            .expect("Single sample");
+        assert!(
+            single == plain,
+            "A single sample differs from the plain frame"
+        );
+        let smooth = renderer
+            .render_supersampled(32, 32, 16)
+            .expect("16 samples");
+        let shades = |pixels: &[u8]| {
+            let mut shades: Vec<_> = pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2]]).collect();
+            shades.sort_unstable();
+            shades.dedup();
+            shades.len()
+        };
+        let (plain, smooth) = (shades(&plain), shades(&smooth));
+        assert!(
+            smooth > plain,
+            "{smooth} shades supersampled, {plain} in the plain frame"
+        );
+    }
+
+    /// The Halton sequence in base 2 halves the gaps it leaves, and every element is in [0, 1)
+    #[test]
+    fn halton_fills_unit_interval() {
+        let elements: Vec<_> = (0..4).map(|i| halton(i, 2)).collect();
+        assert_eq!(elements, [0.0, 0.5, 0.25, 0.75], "Base 2");
+        assert!(
+            (0..64)
+                .map(|i| halton(i, 3))
+                .all(|x| (0.0..1.0).contains(&x)),
+            "Base 3"
+        );
+    }
+
+    /// Mirroring along one axis turns the model matrix's determinant negative, mirroring along
+    /// two is a rotation
+    #[test]
+    fn mirrored_instances() {
+        let mirrored = |scale: glam::Vec3| {
+            Instance {
+                scale,
+                ..Instance::IDENTITY
+            }
+            .is_mirrored()
+        };
+        assert!(mirrored(glam::vec3(-1.0, 1.0, 1.0)), "Mirrored along X");
+        assert!(
+            mirrored(glam::vec3(2.0, 2.0, -0.5)),
+            "Scaled and mirrored along Z"
+        );
+        assert!(
+            !mirrored(glam::vec3(-1.0, -1.0, 1.0)),
+            "Mirrored along X and Y"
+        );
+        assert!(!mirrored(glam::Vec3::splat(2.0)), "Scaled");
+        let instance = Instance {
+            position: glam::Vec3::X,
+            scale: glam::vec3(-2.0, 1.0, 1.0),
+            ..Instance::IDENTITY
+        };
+        let moved = instance.model_matrix().transform_point3(glam::Vec3::ONE);
+        assert_eq!(moved, glam::vec3(-1.0, 1.0, 1.0), "Scaled before moved");
+    }
+
+    /// A cube over the plane mirrored along X looks the same as the original, as both are
+    /// symmetric: its outside is still drawn and lit, not the faces behind it
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn mirrored_instance_shows_same_faces() {
+        let mut renderer = renderer(&SceneOptions::default());
+        renderer
+            .scene
+            .add_mesh(cube::Cube::new_with_normals("mirrored_cube", &renderer.device).into());
+        let mut frame = |instance: Instance| {
+            renderer.instances = vec![instance];
+            renderer.instance_buffer =
+                create_instance_buffer(&renderer.device, &renderer.instances);
+            let target = renderer.render_to_texture(16, 16);
+            renderer.read_pixels(&target).expect("Read back the frame")
+        };
+        let original = frame(Instance::IDENTITY);
+        let mirrored = frame(Instance {
+            scale: glam::vec3(-1.0, 1.0, 1.0),
+            ..Instance::IDENTITY
+        });
+        let differing = original
+            .iter()
+            .zip(&mirrored)
+            .filter(|(a, b)| a.abs_diff(**b) > 2)
+            .count();
+        assert_eq!(differing, 0, "Channels differing from the unmirrored frame");
+    }
+
+    /// A billboard's image faces the camera: its +Z towards the eye and +Y up on screen
+    #[test]
+    fn billboard_faces_camera() {
+        let camera =
+            camera::Camera::new([3.0, 2.0, -4.0], [0.0, 1.0, 0.0], glam::Vec3::Y, 1.0, 1.0);
+        let instance = Instance::facing(&camera, glam::Vec3::ONE, 2.0);
+        let towards_eye = (camera.eye - camera.target).normalize();
+        let normal = instance.rotation * glam::Vec3::Z;
+        assert!(normal.abs_diff_eq(towards_eye, 1e-5), "Normal {normal}");
+        let up = instance.rotation * glam::Vec3::Y;
+        assert!(up.dot(glam::Vec3::Y) > 0.0, "Upside down, up is {up}");
+        assert!(up.dot(towards_eye).abs() < 1e-5, "Up {up} not on screen");
+        assert_eq!(instance.scale, glam::Vec3::splat(2.0), "Scale");
+        assert!(!instance.is_mirrored(), "Mirrored");
+    }
+
+    /// A billboard over the plane covers the center of the frame seen from the front and from
+    /// the side, where a quad that didn't turn would be edge on
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn billboard_turns_with_camera() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let red = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            1,
+            1,
+            image::Rgba([255, 0, 0, 255]),
+        ));
+        let texture =
+            texture::Texture::from_image(&renderer.device, &renderer.queue, &red, Some("red"))
+                .expect("Solid red texture");
+        let position = glam::vec3(0.0, 1.0, 0.0);
+        renderer.scene.camera.target = position;
+        renderer.scene.camera.eye = position + glam::Vec3::Z * 4.0;
+        renderer.add_billboards(&[position], 1.0, texture);
+        for turn in [0.0, std::f32::consts::FRAC_PI_2] {
+            let eye = glam::Quat::from_rotation_y(turn) * glam::Vec3::Z * 4.0;
+            renderer.scene.camera.eye = position + eye;
+            let pixel = center_pixel(&renderer);
+            assert_eq!(pixel, [255, 0, 0, 255], "Center turned by {turn}");
+        }
+    }
+
+    /// A decal stamped at the center of the plane shows its image there over the projected
+    /// one, which still covers the rest of the plane
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn decal_covers_projection() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let frame = |renderer: &Renderer| {
+            let target = renderer.render_to_texture(9, 9);
+            renderer.read_pixels(&target).expect("Read back the frame")
+        };
+        let before = frame(&renderer);
+        let (width, height) = {
+            let image = &renderer.scene.active_material().diffuse_texture;
+            (image.width(), image.height())
+        };
+        let red = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+        let texture = texture::Texture::from_image(
+            &renderer.device,
+            &renderer.queue,
+            &image::DynamicImage::ImageRgba8(red),
+            Some("red"),
+        )
+        .expect("Solid red texture");
+        let camera = renderer.scene.camera;
+        let (origin, dir) = camera.screen_ray(glam::vec2(4.5, 4.5), glam::vec2(9.0, 9.0));
+        let hit = renderer.scene.meshes[0]
+            .raycast(origin, dir)
+            .expect("Center ray hits the plane");
+        renderer
+            .place_decal(hit.position, hit.normal, texture, 0.5)
+            .expect("Place the decal");
+        let after = frame(&renderer);
+        let center = (4 * 9 + 4) * 4;
+        let pixel = &after[center..center + 4];
+        assert!(
+            pixel[0] > 150 && pixel[1] < 30 && pixel[2] < 30,
+            "Center {pixel:?}"
+        );
+        let changed = before
+            .chunks_exact(4)
+            .zip(after.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > 2))
+            .count();
+        assert!(
+            changed < 9,
+            "{changed} pixels changed, not only around the decal"
+        );
+    }
+
+    /// The G-buffer's color is the plain frame, and at the center it holds the up-facing test
+    /// plane's normal, where the projector's image center lands and the camera's distance
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn gbuffer_matches_frame() {
+        let renderer = renderer(&SceneOptions::default());
+        let gbuffer = renderer.render_gbuffer(9, 9).expect("Render the G-buffer");
+        let target = renderer.render_to_texture(9, 9);
+        let frame = renderer.read_pixels(&target).expect("Read back the frame");
+        assert!(
+            gbuffer.color.as_raw() == &frame,
+            "Color differs from the frame"
+        );
+
+        let normal = glam::Vec4::from(gbuffer.normal.get_pixel(4, 4).0);
+        assert!(
+            normal.abs_diff_eq(glam::Vec4::new(0.0, 1.0, 0.0, 1.0), 1e-4),
+            "Normal {normal}"
+        );
+        let camera = renderer.scene.camera;
+        let (origin, dir) = camera.screen_ray(glam::vec2(4.5, 4.5), glam::vec2(9.0, 9.0));
+        let hit = renderer.scene.meshes[0]
+            .raycast(origin, dir)
+            .expect("Center ray hits the plane");
+        let uv = glam::Vec2::from(gbuffer.uv.get_pixel(4, 4).0);
+        let expected = renderer
+            .scene
+            .projector
+            .project_point(hit.position)
+            .expect("Projected");
+        assert!(
+            uv.abs_diff_eq(expected, 1e-3),
+            "Texture coordinates {uv} instead of {expected}"
+        );
+        let [depth] = gbuffer.depth.get_pixel(4, 4).0;
+        let expected = camera.eye.distance(hit.position);
+        assert!(
+            (depth - expected).abs() < 1e-3,
+            "Depth {depth} instead of {expected}"
+        );
+        assert_eq!(
+            gbuffer.normal.get_pixel(0, 0).0[3],
+            0.0,
+            "Normal alpha of the corner"
+        );
+    }
+
+    /// The schematic draws the projector's diamond in yellow and leaves the main camera as it
+    /// was
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn schematic_shows_projector() {
+        let mut renderer = renderer(&SceneOptions::default());
+        let camera = renderer.scene.camera;
+        let target = renderer.render_schematic(64, 20.0, glam::Vec3::Y);
+        let pixels = renderer
+            .read_pixels(&target)
+            .expect("Read back the schematic");
+        let icon = pixels
+            .chunks_exact(4)
+            .any(|pixel| pixel[0] > 200 && pixel[1] > 200 && pixel[2] < 50);
+        assert!(icon, "No projector icon in the schematic");
+        assert_eq!(
+            renderer.scene.camera.eye, camera.eye,
+            "Camera after the schematic"
+        );
+        assert!(renderer.frustum.is_none(), "Frustum outline left on");
+    }
 }
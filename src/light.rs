@@ -0,0 +1,71 @@
+/// Directional light shading the meshes, so their shape still reads where the projected image is
+/// flat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    /// Direction the light travels in, it doesn't have to be normalized
+    pub direction: glam::Vec3,
+    /// Linear color
+    pub color: glam::Vec3,
+    /// Brightness of surfaces facing away from the light, which would be black otherwise
+    pub ambient: f32,
+}
+
+impl Light {
+    /// White light slanting down onto the scene
+    pub const DEFAULT: Self = Self {
+        direction: glam::vec3(-0.3, -1.0, -0.5),
+        color: glam::Vec3::ONE,
+        ambient: 0.3,
+    };
+
+    /// How brightly a surface facing along `normal` is lit, mirroring `shade` in the shader
+    pub fn intensity(&self, normal: glam::Vec3) -> f32 {
+        normal
+            .normalize_or_zero()
+            .dot(-self.direction.normalize_or_zero())
+            .max(self.ambient)
+    }
+}
+
+/// What the shaders need to know about the [`Light`]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    /// Normalized
+    pub direction: [f32; 3],
+    pub ambient: f32,
+    pub color: [f32; 3],
+    /// Pads the uniform to 16 bytes like WGSL does
+    _padding: u32,
+}
+
+impl From<&Light> for LightUniform {
+    fn from(light: &Light) -> Self {
+        Self {
+            direction: light.direction.normalize_or_zero().into(),
+            ambient: light.ambient,
+            color: light.color.into(),
+            _padding: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A face turned towards the light is lit more than the ambient level of one turned away.
+    /// Checks `Light::intensity`, the CPU mirror of `shade` in the shader; the rendering is
+    /// checked by `lit_surface_is_brighter` in `lib.rs`.
+    #[test]
+    fn facing_the_light_is_brighter() {
+        let light = Light::DEFAULT;
+        let facing = light.intensity(-light.direction);
+        let away = light.intensity(light.direction);
+        assert!(
+            (facing - 1.0).abs() < 1e-5,
+            "Face pointing at the light has intensity {facing} instead of 1"
+        );
+        assert_eq!(away, light.ambient, "Face pointing away from the light");
+    }
+}
@@ -1,5 +1,13 @@
 use lib::run;
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--verify") {
+        env_logger::init();
+        if let Err(e) = pollster::block_on(lib::run_self_test()) {
+            eprintln!("Self test failed: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
     pollster::block_on(run());
 }
@@ -1,3 +1,4 @@
+use wgpu::util::DeviceExt;
 use wgpu::util::RenderEncoder;
 
 use crate::texture;
@@ -10,8 +11,8 @@ pub struct ModelVertex {
     pub normal: [f32; 3],
 }
 
-impl ModelVertex {
-    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+impl Vertex for ModelVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<ModelVertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
@@ -27,7 +28,7 @@ impl ModelVertex {
                     format: wgpu::VertexFormat::Float32x2,
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x3,
                 },
@@ -36,6 +37,209 @@ impl ModelVertex {
     }
 }
 
+/// Vertex of a [`LineMesh`], drawn unshaded in its linear `color`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+impl Vertex for LineVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+/// Convert `vertices` to the [`ModelVertex`] layout the pipelines draw meshes with
+pub fn model_vertices<V: Copy + Into<ModelVertex>>(vertices: &[V]) -> Vec<ModelVertex> {
+    vertices.iter().map(|&v| v.into()).collect()
+}
+
+/// Approximate minimal sphere `(center, radius)` enclosing `points`, using Ritter's algorithm.
+/// Tighter than the sphere around an AABB, and at most a few percent larger than optimal.
+pub fn bounding_sphere(points: &[glam::Vec3]) -> (glam::Vec3, f32) {
+    let Some(&first) = points.first() else {
+        return (glam::Vec3::ZERO, 0.0);
+    };
+    let farthest_from = |from: glam::Vec3| {
+        points
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                a.distance_squared(from)
+                    .total_cmp(&b.distance_squared(from))
+            })
+            .unwrap_or(from)
+    };
+    // Start from the sphere spanning two roughly opposite points...
+    let a = farthest_from(first);
+    let b = farthest_from(a);
+    let mut center = (a + b) * 0.5;
+    let mut radius = a.distance(b) * 0.5;
+    // ...and grow it just enough to take in any point left outside
+    for &p in points {
+        let distance = p.distance(center);
+        if distance > radius {
+            let new_radius = (radius + distance) * 0.5;
+            center += (p - center) * ((new_radius - radius) / distance);
+            radius = new_radius;
+        }
+    }
+    (center, radius)
+}
+
+/// Axis-aligned bounding box
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: glam::Vec3,
+    pub max: glam::Vec3,
+}
+
+impl Aabb {
+    /// Smallest box containing `points`, empty at the origin if there are none
+    pub fn from_points(points: impl IntoIterator<Item = glam::Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Self {
+                min: glam::Vec3::ZERO,
+                max: glam::Vec3::ZERO,
+            };
+        };
+        let mut aabb = Self {
+            min: first,
+            max: first,
+        };
+        for p in points {
+            aabb.min = aabb.min.min(p);
+            aabb.max = aabb.max.max(p);
+        }
+        aabb
+    }
+
+    pub fn center(&self) -> glam::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half the size along each axis
+    pub fn extents(&self) -> glam::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Smallest box containing both
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Where a ray meets a mesh, see [`Mesh::raycast`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// Distance along the ray, in multiples of its direction
+    pub t: f32,
+    pub position: glam::Vec3,
+    /// Which triangle of [`Mesh::indices`] was hit
+    pub triangle_index: usize,
+    /// Weights of the triangle's three corners at `position`
+    pub barycentric: glam::Vec3,
+    /// Unit normal of the triangle, on the side its corners go counter-clockwise around
+    pub normal: glam::Vec3,
+}
+
+/// Möller–Trumbore ray-triangle intersection, hitting either side.  Returns `(t, u, v)` where
+/// `u` and `v` are the barycentric weights of `b` and `c`.
+fn intersect_triangle(
+    origin: glam::Vec3,
+    dir: glam::Vec3,
+    [a, b, c]: [glam::Vec3; 3],
+) -> Option<(f32, f32, f32)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = dir.cross(edge2);
+    let det = edge1.dot(p);
+    // Parallel to the triangle's plane
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - a;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(edge1);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(q) * inv_det;
+    (t > 0.0).then_some((t, u, v))
+}
+
+/// Nearest triangle of a triangle list in front of `origin` along `dir`.  Tests every
+/// triangle, which is fine for picking but not for many rays a frame.
+pub fn raycast(
+    vertices: &[ModelVertex],
+    indices: &[u32],
+    origin: glam::Vec3,
+    dir: glam::Vec3,
+) -> Option<RayHit> {
+    indices
+        .chunks_exact(3)
+        .enumerate()
+        .filter_map(|(triangle_index, triangle)| {
+            let corners =
+                [0, 1, 2].map(|i| glam::Vec3::from(vertices[triangle[i] as usize].position));
+            let (t, u, v) = intersect_triangle(origin, dir, corners)?;
+            Some(RayHit {
+                t,
+                position: origin + dir * t,
+                triangle_index,
+                barycentric: glam::vec3(1.0 - u - v, u, v),
+                normal: (corners[1] - corners[0])
+                    .cross(corners[2] - corners[0])
+                    .normalize_or_zero(),
+            })
+        })
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+}
+
+/// Smooth per-vertex normals for a triangle list: the sum of the adjacent face normals weighted
+/// by face area, normalized.  Vertices no triangle uses get a zero normal.
+pub fn compute_normals(vertices: &mut [ModelVertex], indices: &[u32]) {
+    let mut normals = vec![glam::Vec3::ZERO; vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] =
+            [0, 1, 2].map(|i| glam::Vec3::from(vertices[triangle[i] as usize].position));
+        // Unnormalized, the cross product is twice the triangle's area
+        let normal = (b - a).cross(c - a);
+        for &i in triangle {
+            normals[i as usize] += normal;
+        }
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.normal = normal.normalize_or_zero().into();
+    }
+}
+
 pub trait Vertex {
     fn desc() -> wgpu::VertexBufferLayout<'static>;
 }
@@ -45,10 +249,24 @@ pub struct Model {
     pub materials: Vec<Material>,
 }
 
+impl Model {
+    /// Bounds of all meshes, `None` if there are none
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.meshes
+            .iter()
+            .map(Mesh::aabb)
+            .reduce(|a, b| a.merge(&b))
+    }
+}
+
 pub struct Material {
     pub name: String,
     pub diffuse_texture: texture::Texture,
     pub bind_group: wgpu::BindGroup,
+    /// Composite with premultiplied alpha (`One`, `OneMinusSrcAlpha`).  The color is multiplied
+    /// by alpha in the shader, after sRGB decoding, so textures are loaded with straight alpha as
+    /// usual.  Avoids dark halos around anti-aliased edges.
+    pub premultiplied: bool,
 }
 
 impl Material {
@@ -58,7 +276,23 @@ impl Material {
         device: &wgpu::Device,
         bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Self {
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let texture_bind_group = Self::create_bind_group(&texture, device, bind_group_layout);
+
+        Self {
+            name: name.to_string(),
+            diffuse_texture: texture,
+            bind_group: texture_bind_group,
+            premultiplied: false,
+        }
+    }
+
+    /// Bind group sampling `texture` through `bind_group_layout`
+    pub fn create_bind_group(
+        texture: &texture::Texture,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
@@ -71,13 +305,21 @@ impl Material {
                 },
             ],
             label: None,
-        });
+        })
+    }
 
-        Self {
-            name: name.to_string(),
-            diffuse_texture: texture,
-            bind_group: texture_bind_group,
-        }
+    /// Reallocate the texture at a new size and format, see [`texture::Texture::recreate`], and
+    /// swap in a bind group for it.  Bind groups cached elsewhere still reference the old view.
+    pub fn recreate_texture(
+        &mut self,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) {
+        self.diffuse_texture.recreate(device, width, height, format);
+        self.bind_group = Self::create_bind_group(&self.diffuse_texture, device, bind_group_layout);
     }
 }
 
@@ -87,15 +329,110 @@ pub struct Mesh {
     pub index_buffer: wgpu::Buffer,
     pub num_elements: u32,
     pub material: usize,
+    /// Center and radius of a sphere enclosing the mesh, see [`bounding_sphere`].  Framing the
+    /// mesh with it, as `Projector::aim_at_mesh` does, is tighter than with the AABB.
+    pub bounding_sphere: (glam::Vec3, f32),
+    /// CPU copy of what was uploaded, for anything that needs the geometry after construction
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Upload `vertices` and triangle list `indices` as a mesh drawn with `material`.  The
+    /// vertices are converted to [`ModelVertex`] first, which is the layout the pipelines draw
+    /// meshes with and what's kept on the CPU.
+    pub fn from_vertices<V: Copy + Into<ModelVertex>>(
+        name: &str,
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        material: usize,
+    ) -> Self {
+        let vertices = model_vertices(vertices);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Vertex Buffer", name)),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Index Buffer", name)),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let points: Vec<glam::Vec3> = vertices.iter().map(|v| v.position.into()).collect();
+        Self {
+            name: format!("{:?} mesh", name),
+            vertex_buffer,
+            index_buffer,
+            num_elements: indices.len() as u32,
+            material,
+            bounding_sphere: bounding_sphere(&points),
+            vertices,
+            indices: indices.to_vec(),
+        }
+    }
+
+    /// Bounds of the vertices in model space
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.vertices.iter().map(|v| glam::Vec3::from(v.position)))
+    }
+
+    /// Nearest triangle in front of `origin` along `dir`, in model space, see [`raycast`]
+    pub fn raycast(&self, origin: glam::Vec3, dir: glam::Vec3) -> Option<RayHit> {
+        raycast(&self.vertices, &self.indices, origin, dir)
+    }
+
+    /// Replace the normals with [`compute_normals`] and upload the vertices again.  The new
+    /// vertex buffer holds [`ModelVertex`] whatever the mesh was built from.
+    pub fn recompute_normals(&mut self, device: &wgpu::Device) {
+        compute_normals(&mut self.vertices, &self.indices);
+        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", self.name)),
+            contents: bytemuck::cast_slice(&self.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+    }
+}
+
+/// Line list mesh like [`crate::cube::Grid`], drawn with the line pipeline.  Its indices pair up
+/// vertices instead of forming triangles, so unlike a [`Mesh`] it has no material and can't be
+/// picked.
+pub struct LineMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_elements: u32,
+}
+
+impl LineMesh {
+    pub fn new(
+        name: &str,
+        device: &wgpu::Device,
+        vertices: &[LineVertex],
+        indices: &[u32],
+    ) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Vertex Buffer", name)),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Index Buffer", name)),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_elements: indices.len() as u32,
+        }
+    }
 }
 
 pub trait DrawModel<'a> {
-    fn draw_mesh(
-        &mut self,
-        mesh: &'a Mesh,
-        material: &'a Material,
-        camera_bind_group: &'a wgpu::BindGroup,
-    );
     fn draw_mesh_instanced(
         &mut self,
         mesh: &'a Mesh,
@@ -103,10 +440,9 @@ pub trait DrawModel<'a> {
         instances: std::ops::Range<u32>,
         camera_bind_group: &'a wgpu::BindGroup,
     );
-    fn draw_model(&mut self, model: &'a Model, camera_bind_group: &'a wgpu::BindGroup);
-    fn draw_model_instanced(
+    fn draw_line_mesh_instanced(
         &mut self,
-        model: &'a Model,
+        lines: &'a LineMesh,
         instances: std::ops::Range<u32>,
         camera_bind_group: &'a wgpu::BindGroup,
     );
@@ -116,15 +452,6 @@ impl<'a, 'b> DrawModel<'b> for wgpu::RenderPass<'a>
 where
     'b: 'a,
 {
-    fn draw_mesh(
-        &mut self,
-        mesh: &'a Mesh,
-        material: &'a Material,
-        camera_bind_group: &'a wgpu::BindGroup,
-    ) {
-        self.draw_mesh_instanced(mesh, material, 0..1, camera_bind_group);
-    }
-
     fn draw_mesh_instanced(
         &mut self,
         mesh: &'a Mesh,
@@ -139,19 +466,147 @@ where
         self.draw_indexed(0..mesh.num_elements, 0, instances);
     }
 
-    fn draw_model(&mut self, model: &'b Model, camera_bind_group: &'b wgpu::BindGroup) {
-        self.draw_model_instanced(model, 0..1, camera_bind_group);
-    }
-
-    fn draw_model_instanced(
+    fn draw_line_mesh_instanced(
         &mut self,
-        model: &'b Model,
+        lines: &'b LineMesh,
         instances: std::ops::Range<u32>,
         camera_bind_group: &'b wgpu::BindGroup,
     ) {
-        for mesh in &model.meshes {
-            let material = &model.materials[mesh.material];
-            self.draw_mesh_instanced(mesh, material, instances.clone(), camera_bind_group);
+        self.set_vertex_buffer(0, lines.vertex_buffer.slice(..));
+        self.set_index_buffer(lines.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.set_bind_group(1, camera_bind_group, &[]);
+        self.draw_indexed(0..lines.num_elements, 0, instances);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Area weighted normals of a tetrahedron with its right angle corner at the origin: the
+    /// three unit faces meeting there balance the slanted face at the other corners
+    #[test]
+    fn tetrahedron_normals() {
+        let mut vertices = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]
+        .map(|position| ModelVertex {
+            position,
+            tex_coords: [0.0, 0.0],
+            normal: [0.0, 0.0, 0.0],
+        });
+        #[rustfmt::skip]
+        let indices = [
+            0, 2, 1,
+            0, 1, 3,
+            0, 3, 2,
+            1, 2, 3,
+        ];
+        compute_normals(&mut vertices, &indices);
+        let expected = [
+            -glam::Vec3::ONE.normalize(),
+            glam::Vec3::X,
+            glam::Vec3::Y,
+            glam::Vec3::Z,
+        ];
+        for (i, (vertex, expected)) in vertices.iter().zip(expected).enumerate() {
+            let normal = glam::Vec3::from(vertex.normal);
+            assert!(
+                normal.abs_diff_eq(expected, 1e-5),
+                "Tetrahedron vertex {i} has normal {normal} instead of {expected}"
+            );
+        }
+    }
+
+    /// Points spread over a sphere of radius 2 are all inside the bounding sphere, which is
+    /// close to that sphere rather than the one around their bounding box
+    #[test]
+    fn sphere_encloses_point_cloud() {
+        let center = glam::vec3(1.0, -2.0, 3.0);
+        // Fibonacci lattice, evenly covering the sphere without randomness
+        let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+        let points: Vec<_> = (0..200)
+            .map(|i| {
+                let y = 1.0 - 2.0 * (i as f32 + 0.5) / 200.0;
+                let r = (1.0 - y * y).sqrt();
+                let angle = golden_angle * i as f32;
+                center + 2.0 * glam::vec3(r * angle.cos(), y, r * angle.sin())
+            })
+            .collect();
+        let (sphere_center, radius) = bounding_sphere(&points);
+        for point in &points {
+            let distance = point.distance(sphere_center);
+            assert!(
+                distance <= radius + 1e-4,
+                "{point} is {distance} from the center, outside radius {radius}"
+            );
+        }
+        assert!(radius < 2.2, "Radius {radius} is over 10% larger than 2");
+    }
+
+    /// Straight down onto two stacked quads, the ray stops at the upper one at y = -1
+    #[test]
+    fn ray_hits_nearest_triangle() {
+        let vertices = [-3.0, -1.0].map(|y| {
+            [
+                [-2.0, y, -2.0],
+                [-2.0, y, 2.0],
+                [2.0, y, 2.0],
+                [2.0, y, -2.0],
+            ]
+            .map(|position| ModelVertex {
+                position,
+                tex_coords: [0.0, 0.0],
+                normal: [0.0, 1.0, 0.0],
+            })
+        });
+        #[rustfmt::skip]
+        let indices = [
+            0, 1, 2,
+            2, 3, 0,
+            4, 5, 6,
+            6, 7, 4,
+        ];
+        let hit = raycast(
+            vertices.as_flattened(),
+            &indices,
+            glam::vec3(0.5, 5.0, 0.25),
+            glam::Vec3::NEG_Y,
+        )
+        .expect("Ray misses the quads");
+        assert!(
+            hit.position.abs_diff_eq(glam::vec3(0.5, -1.0, 0.25), 1e-5)
+                && (hit.t - 6.0).abs() < 1e-5,
+            "Ray hits the quads at {} (t = {})",
+            hit.position,
+            hit.t
+        );
+        assert!(hit.triangle_index >= 2, "Ray hits the lower quad");
+        assert_eq!(hit.normal, glam::Vec3::Y, "Normal of the quad");
+    }
+
+    /// A triangle of `SimpleVertex` becomes three `ModelVertex` with the same positions and
+    /// texture coordinates, facing +Z
+    #[test]
+    fn triangle_mesh() {
+        let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]].map(|position| {
+            crate::cube::SimpleVertex {
+                position,
+                tex_coords: [position[0], position[1]],
+            }
+        });
+        let vertices = model_vertices(&triangle);
+        assert_eq!(vertices.len(), 3, "Triangle vertices");
+        for (vertex, simple) in vertices.iter().zip(&triangle) {
+            assert_eq!(vertex.position, simple.position, "Converted position");
+            assert_eq!(
+                vertex.tex_coords, simple.tex_coords,
+                "Converted texture coordinates"
+            );
+            assert_eq!(vertex.normal, [0.0, 0.0, 1.0], "Converted normal");
         }
     }
 }
@@ -2,7 +2,6 @@ use crate::{model, texture};
 use bytemuck::bytes_of_mut;
 use cfg_if::cfg_if;
 use std::io::{BufReader, Cursor};
-use wgpu::util::DeviceExt;
 
 #[cfg(target_arch = "wasm32")]
 fn format_url(file_name: &str) -> reqwest::Url {
@@ -60,9 +59,51 @@ pub async fn load_texture(
     file_name: &str,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
+    oversized: texture::Oversized,
 ) -> anyhow::Result<texture::Texture> {
     let data = load_binary(file_name).await?;
-    texture::Texture::from_bytes(device, queue, &data, file_name)
+    texture::Texture::from_bytes(device, queue, &data, file_name, oversized)
+}
+
+/// Load a texture from an arbitrary path on disk.  On wasm the path is resolved like
+/// [`load_binary`].
+pub async fn load_texture_file(
+    path: &str,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    oversized: texture::Oversized,
+) -> anyhow::Result<texture::Texture> {
+    cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            let data = load_binary(path).await?;
+        } else {
+            let data = std::fs::read(path)?;
+        }
+    }
+    texture::Texture::from_bytes(device, queue, &data, path, oversized)
+}
+
+/// Load every image in `dir`, in file name order, as the frames of an array texture.  See
+/// [`texture::Texture::from_image_sequence`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_image_sequence(
+    dir: &str,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> anyhow::Result<texture::Texture> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    let frames = paths
+        .iter()
+        .map(|path| {
+            image::open(path)
+                .map_err(|e| anyhow::anyhow!("Couldn't load frame {}: {e}", path.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    texture::Texture::from_image_sequence(device, queue, &frames, dir)
 }
 
 pub async fn load_model(
@@ -83,41 +124,48 @@ pub async fn load_model(
             ..Default::default()
         },
         |p| async move {
-            let mat_text = load_string(&p).await.unwrap();
+            let mat_text = load_string(&p)
+                .await
+                .map_err(|_| tobj::LoadError::OpenFileFailed)?;
             tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
         },
     )
     .await?;
 
+    // A model without its materials still shows its shape, in plain white
+    let obj_materials = obj_materials.unwrap_or_else(|e| {
+        log::warn!("Couldn't load the materials of {file_name}, drawing it white: {e}");
+        Vec::new()
+    });
     let mut materials = Vec::new();
-    for m in obj_materials? {
-        let diffuse_texture_name = m.diffuse_texture.unwrap_or("unknown_diffuse_texture".to_string());
-        let diffuse_texture = load_texture(&diffuse_texture_name, device, queue).await?;
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: None,
-        });
-
-        materials.push(model::Material {
-            name: m.name,
+    for m in obj_materials {
+        let diffuse_texture = match &m.diffuse_texture {
+            Some(name) => load_texture(name, device, queue, texture::Oversized::default()).await?,
+            None => {
+                log::warn!(
+                    "Material {} has no diffuse texture, drawing it white",
+                    m.name
+                );
+                white_texture(device, queue)?
+            }
+        };
+        materials.push(model::Material::new(
+            &m.name,
             diffuse_texture,
-            bind_group,
-        })
+            device,
+            layout,
+        ));
+    }
+    // Meshes refer to their material by index, so there has to be one to fall back on
+    if materials.is_empty() {
+        let texture = white_texture(device, queue)?;
+        materials.push(model::Material::new("white", texture, device, layout));
     }
 
     let meshes = models
         .into_iter()
         .map(|m| {
+            // Texture coordinates and normals are optional in OBJ files
             let vertices = (0..m.mesh.positions.len() / 3)
                 .map(|i| model::ModelVertex {
                     position: [
@@ -125,36 +173,61 @@ pub async fn load_model(
                         m.mesh.positions[i * 3 + 1],
                         m.mesh.positions[i * 3 + 2],
                     ],
-                    tex_coords: [m.mesh.texcoords[i * 2], m.mesh.texcoords[i * 2 + 1]],
-                    normal: [
-                        m.mesh.normals[i * 3],
-                        m.mesh.normals[i * 3 + 1],
-                        m.mesh.normals[i * 3 + 2],
-                    ],
+                    tex_coords: match m.mesh.texcoords.get(i * 2..i * 2 + 2) {
+                        Some(&[u, v]) => [u, v],
+                        _ => [0.0, 0.0],
+                    },
+                    normal: match m.mesh.normals.get(i * 3..i * 3 + 3) {
+                        Some(&[x, y, z]) => [x, y, z],
+                        _ => [0.0, 0.0, 0.0],
+                    },
                 })
                 .collect::<Vec<_>>();
+            let material = match m.mesh.material_id {
+                Some(id) if id >= materials.len() => {
+                    log::warn!("Mesh {} has no material {id}, drawing it white", m.name);
+                    0
+                }
+                id => id.unwrap_or(0),
+            };
+            let (vertices, indices) = if m.mesh.normals.is_empty() {
+                flat_shaded(&vertices, &m.mesh.indices)
+            } else {
+                (vertices, m.mesh.indices)
+            };
 
-            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("{:?} Vertex Buffer", file_name)),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-
-            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("{:?} Index Buffer", file_name)),
-                contents: bytemuck::cast_slice(&m.mesh.indices),
-                usage: wgpu::BufferUsages::INDEX,
-            });
-
-            model::Mesh {
-                name: file_name.to_string(),
-                vertex_buffer,
-                index_buffer,
-                num_elements: m.mesh.indices.len() as u32,
-                material: m.mesh.material_id.unwrap_or(0),
-            }
+            model::Mesh::from_vertices(&m.name, device, &vertices, &indices, material)
         })
         .collect::<Vec<_>>();
 
     Ok(model::Model { meshes, materials })
 }
+
+/// Stand-in for a missing diffuse texture, so the mesh still shows its shading
+fn white_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<texture::Texture> {
+    let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+        1,
+        1,
+        image::Rgba([255, 255, 255, 255]),
+    ));
+    texture::Texture::from_image(device, queue, &image, Some("white"))
+}
+
+/// Unshare the vertices of triangle list `indices` so each triangle gets its own, with the
+/// triangle's normal.  Used for meshes that come without normals.
+fn flat_shaded(
+    vertices: &[model::ModelVertex],
+    indices: &[u32],
+) -> (Vec<model::ModelVertex>, Vec<u32>) {
+    let vertices: Vec<_> = indices
+        .chunks_exact(3)
+        .flat_map(|triangle| {
+            let corners = [0, 1, 2].map(|i| vertices[triangle[i] as usize]);
+            let [a, b, c] = corners.map(|v| glam::Vec3::from(v.position));
+            let normal = (b - a).cross(c - a).normalize_or_zero().into();
+            corners.map(|v| model::ModelVertex { normal, ..v })
+        })
+        .collect();
+    let indices = (0..vertices.len() as u32).collect();
+    (vertices, indices)
+}
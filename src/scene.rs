@@ -0,0 +1,114 @@
+use crate::{camera, light, model};
+
+/// Index of a mesh in [`Scene::meshes`]
+pub type MeshId = usize;
+
+/// What is rendered: the meshes, the images that can be projected onto them, the projector, the
+/// light, the viewpoint and the background.  How it is drawn is up to the renderer.
+pub struct Scene {
+    pub meshes: Vec<model::Mesh>,
+    pub materials: Vec<model::Material>,
+    /// Index into `materials` of the projected image
+    pub active_material: usize,
+    pub camera: camera::Camera,
+    /// Where the image is projected from, independently of `camera`
+    pub projector: camera::Projector,
+    /// Projectors blended in place of `projector` once any are added, each showing its own
+    /// image, see `State::add_projector`
+    pub projectors: Vec<camera::Projector>,
+    /// Shades the meshes underneath the projected image
+    pub light: light::Light,
+    /// Linear clear color, see [`crate::color`] to convert from sRGB
+    pub background: wgpu::Color,
+}
+
+impl Scene {
+    /// A scene projecting `material` from the viewpoint of `camera`, with no meshes yet
+    pub fn new(camera: camera::Camera, material: model::Material, background: wgpu::Color) -> Self {
+        Self {
+            meshes: Vec::new(),
+            materials: vec![material],
+            active_material: 0,
+            projector: camera::Projector::from_camera(&camera),
+            projectors: Vec::new(),
+            light: light::Light::DEFAULT,
+            camera,
+            background,
+        }
+    }
+
+    /// Add a mesh and return its index
+    pub fn add_mesh(&mut self, mesh: model::Mesh) -> MeshId {
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    /// Remove the mesh at `index`, shifting later meshes down
+    pub fn remove_mesh(&mut self, index: MeshId) -> Option<model::Mesh> {
+        (index < self.meshes.len()).then(|| self.meshes.remove(index))
+    }
+
+    pub fn mesh(&self, index: MeshId) -> Option<&model::Mesh> {
+        self.meshes.get(index)
+    }
+
+    /// All meshes with their ids, e.g. to list them in a UI
+    pub fn iter_meshes(&self) -> impl Iterator<Item = (MeshId, &model::Mesh)> {
+        self.meshes.iter().enumerate()
+    }
+
+    /// First mesh whose name is `name`
+    pub fn mesh_by_name(&self, name: &str) -> Option<(MeshId, &model::Mesh)> {
+        self.iter_meshes().find(|(_, mesh)| mesh.name == name)
+    }
+
+    /// Add a material and return its index
+    pub fn add_material(&mut self, material: model::Material) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Remove the material at `index`.  The last material can't be removed since something
+    /// always has to be projected.
+    pub fn remove_material(&mut self, index: usize) -> Option<model::Material> {
+        if index >= self.materials.len() || self.materials.len() == 1 {
+            return None;
+        }
+        let material = self.materials.remove(index);
+        if self.active_material > index || self.active_material == self.materials.len() {
+            self.active_material -= 1;
+        }
+        Some(material)
+    }
+
+    pub fn material(&self, index: usize) -> Option<&model::Material> {
+        self.materials.get(index)
+    }
+
+    /// All materials with their indices
+    pub fn iter_materials(&self) -> impl Iterator<Item = (usize, &model::Material)> {
+        self.materials.iter().enumerate()
+    }
+
+    /// First material whose name is `name`
+    pub fn material_by_name(&self, name: &str) -> Option<(usize, &model::Material)> {
+        self.iter_materials()
+            .find(|(_, material)| material.name == name)
+    }
+
+    /// The projected material
+    pub fn active_material(&self) -> &model::Material {
+        &self.materials[self.active_material]
+    }
+
+    /// Replace the projected material
+    pub fn set_active_material(&mut self, material: model::Material) {
+        self.materials[self.active_material] = material;
+    }
+
+    /// Project the next material, wrapping around
+    pub fn cycle_material(&mut self) -> &model::Material {
+        self.active_material = (self.active_material + 1) % self.materials.len();
+        self.active_material()
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::{ensure, Context};
+
+use crate::{camera, cube, light, model, SceneOptions};
+
+const SIZE: u32 = 64;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const CLEAR_COLOR: wgpu::Color = wgpu::Color::BLACK;
+
+/// Exercise the whole pipeline without a window: check the projection math, add every
+/// primitive to the default scene, render a frame offscreen and check that something other than
+/// the clear color was drawn.  Meant for CI and quick sanity checks on a GPU, see `--verify`.
+pub async fn run_self_test() -> anyhow::Result<()> {
+    check_projection_math()?;
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .context("No adapter available")?;
+    let options = SceneOptions {
+        clear_color: Some(CLEAR_COLOR),
+        ..Default::default()
+    };
+    let mut renderer = crate::Renderer::new(instance, adapter, FORMAT, &options, 1.0).await?;
+
+    let device = &renderer.device;
+    let meshes: Vec<model::Mesh> = vec![
+        cube::Cube::new_with_normals("self_test_cube", device).into(),
+        cube::Plane::new("self_test_plane", device).into(),
+        cube::Billboard::new("self_test_billboard", device).into(),
+        cube::Sphere::new("self_test_sphere", 0.5, 8, 12, true, device).into(),
+        cube::Cylinder::new("self_test_cylinder", 0.5, 1.0, 12, device).into(),
+    ];
+    for mesh in meshes {
+        renderer.scene.add_mesh(mesh);
+    }
+
+    renderer
+        .device
+        .push_error_scope(wgpu::ErrorFilter::Validation);
+    let target = renderer.render_to_texture(SIZE, SIZE);
+    if let Some(e) = renderer.device.pop_error_scope().await {
+        anyhow::bail!("Failed to render the frame: {e}");
+    }
+    let drawn = renderer
+        .read_pixels(&target)?
+        .chunks_exact(4)
+        .filter(|pixel| *pixel != [0, 0, 0, 255])
+        .count();
+    ensure!(drawn > 0, "Rendered frame is entirely the clear color");
+    log::info!("Self test passed, {drawn} pixels drawn");
+    Ok(())
+}
+
+/// The CPU side of the projection, which the shader mirrors: where points land in the projected
+/// image and how brightly surfaces are lit
+fn check_projection_math() -> anyhow::Result<()> {
+    let camera = camera::Camera::new([-6.0, 6.0, 6.0], [0.0, 0.0, 0.0], glam::Vec3::Y, 1.0, 0.5);
+    let projector = camera::Projector::from_camera(&camera);
+    let center = projector
+        .project_point(projector.target)
+        .context("Projector target is outside its image")?;
+    ensure!(
+        center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+        "Projector target lands at {center} instead of the image center"
+    );
+    let behind = projector.eye * 2.0 - projector.target;
+    ensure!(
+        projector.project_point(behind).is_none(),
+        "A point behind the projector is projected"
+    );
+
+    let mut orthographic = camera;
+    orthographic.toggle_projection();
+    let center = orthographic
+        .project_point(camera.target)
+        .context("Camera target is outside the orthographic image")?;
+    ensure!(
+        center.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5),
+        "Orthographic camera target lands at {center} instead of the image center"
+    );
+
+    let light = light::Light::DEFAULT;
+    let lit = light.intensity(-light.direction);
+    let unlit = light.intensity(light.direction);
+    ensure!(
+        (lit - 1.0).abs() < 1e-5,
+        "Surface facing the light has intensity {lit}"
+    );
+    ensure!(
+        unlit == light.ambient,
+        "Surface facing away has intensity {unlit} instead of the ambient {}",
+        light.ambient
+    );
+    Ok(())
+}
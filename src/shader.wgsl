@@ -2,33 +2,89 @@
 
 struct CameraUniform {
     view_proj: mat4x4<f32>,
+    view_pos: vec4<f32>,
+    inv_view_proj: mat4x4<f32>,
 };
 
 @group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
+struct ProjectorUniform {
+    view_proj: mat4x4<f32>,
+    base_color: vec4<f32>,
+    // Nonzero to leave geometry behind the projector unlit instead of mirroring the image onto it
+    reject_behind: u32,
+    // Share in overlaps with other projectors, see `fs_blend`
+    weight: f32,
+    // Nonzero to mirror the image across `u` and `v`
+    flip: vec2<u32>,
+    // Brown-Conrady `k1, k2, p1, p2`
+    distortion: vec4<f32>,
+    // Focal lengths and principal point in texture coordinates, `fx, fy, cx, cy`
+    lens: vec4<f32>,
+    // 0 for perspective, 1 for an equidistant and 2 for an equisolid fisheye, which
+    // `view_proj` only takes to projector view space for
+    fisheye: u32,
+    half_fov: f32,
+    // Nonzero to blend the image over the base color by its alpha
+    alpha_as_mask: u32,
+    // Number of vertices of the mask polygon, 0 for none
+    mask_len: u32,
+    // Mask polygon vertices in texture coordinates, two to a `vec4`.  Length must match
+    // `MaskPolygon::MAX_VERTICES / 2`.
+    mask: array<vec4<f32>, 8>,
+};
+
+@group(2) @binding(0)
+var<uniform> projector: ProjectorUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+};
+
+// Columns of the instance's model matrix, see `InstanceRaw`
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) tex_coords: vec2<f32>,
+    // Divided by `w` per fragment, interpolating texture coordinates instead would bend the
+    // image along the triangle edges
+    @location(0) projector_position: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) world_normal: vec3<f32>,
 };
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
     var out: VertexOutput;
 
     // Calculate vertex position in clip space
-    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * world_position;
 
-    // Calculate vertex position in screen space
-    let ndc = out.clip_position.xyz / out.clip_position.w;
-    let tex_pos = ndc * 0.5 + 0.5;
-    out.tex_coords = vec2<f32>(tex_pos.x, 1.0 - tex_pos.y);
+    // Calculate vertex position as seen from the projector
+    out.projector_position = projector.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    // The cofactor matrix turns normals under any scale.  It is the inverse transpose times the
+    // determinant, whose sign keeps them pointing out of mirrored instances too.
+    let m = mat3x3<f32>(model_matrix[0].xyz, model_matrix[1].xyz, model_matrix[2].xyz);
+    let cofactor = mat3x3<f32>(cross(m[1], m[2]), cross(m[2], m[0]), cross(m[0], m[1]));
+    out.world_normal = sign(determinant(m)) * (cofactor * model.normal);
 
     return out;
 }
@@ -40,10 +96,347 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+struct LightUniform {
+    // Normalized, the way the light travels
+    direction: vec3<f32>,
+    ambient: f32,
+    color: vec3<f32>,
+};
+
+@group(2) @binding(1)
+var<uniform> light: LightUniform;
+
+// Lambert shading of `color`, never darker than the ambient level, see `Light::intensity`
+fn shade(in: VertexOutput, color: vec4<f32>) -> vec4<f32> {
+    let diffuse = max(dot(normalize(in.world_normal), -light.direction), light.ambient);
+    return vec4<f32>(color.rgb * light.color * diffuse, color.a);
+}
+
+// Lens distortion of projector `p` applied to ideal texture coordinates, see
+// `Projector::distort_point`
+fn distort(p: ProjectorUniform, tex_coords: vec2<f32>) -> vec2<f32> {
+    let k1 = p.distortion.x;
+    let k2 = p.distortion.y;
+    let p1 = p.distortion.z;
+    let p2 = p.distortion.w;
+    let xy = (tex_coords - p.lens.zw) / p.lens.xy;
+    let x = xy.x;
+    let y = xy.y;
+    let r2 = x * x + y * y;
+    let radial = 1.0 + k1 * r2 + k2 * r2 * r2;
+    let distorted = vec2<f32>(
+        x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x),
+        y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y,
+    );
+    // Skipped for an ideal lens so it matches the undistorted coordinates exactly
+    let ideal = all(p.distortion == vec4<f32>(0.0));
+    return select(distorted * p.lens.xy + p.lens.zw, tex_coords, ideal);
+}
+
+// Where the direction to `view`, a point in the view space of fisheye projector `p`, lands in
+// its image circle around the principal point, see `Projector::fisheye_tex_coords`.  Directions
+// past half the field of view land outside the image.
+fn fisheye_tex_coords(p: ProjectorUniform, view: vec3<f32>) -> vec2<f32> {
+    let distance = length(view);
+    let theta = acos(clamp(-view.z / distance, -1.0, 1.0));
+    let equisolid = sin(theta * 0.5) / sin(p.half_fov * 0.5);
+    let radius = 0.5 * select(theta / p.half_fov, equisolid, p.fisheye == 2u);
+    let off_axis = length(view.xy);
+    let around = select(view.xy / off_axis, vec2<f32>(0.0), off_axis == 0.0);
+    let tex_coords = p.lens.zw + radius * around * vec2<f32>(p.lens.x / p.lens.y, -1.0);
+    return select(tex_coords, vec2<f32>(-1.0), theta > p.half_fov || distance == 0.0);
+}
+
+// Where a point at `position` in the clip space of projector `p` lands in its image, see
+// `Projector::project_point`
+fn projected_tex_coords(p: ProjectorUniform, position: vec4<f32>) -> vec2<f32> {
+    let ndc = position.xy / position.w;
+    let tex_pos = ndc * 0.5 + 0.5;
+    let perspective = distort(p, vec2<f32>(tex_pos.x, 1.0 - tex_pos.y));
+    let fisheye = fisheye_tex_coords(p, position.xyz);
+    let tex_coords = select(perspective, fisheye, p.fisheye != 0u);
+    return select(tex_coords, 1.0 - tex_coords, p.flip != vec2<u32>(0u));
+}
+
+// Whether `tex_coords` is inside the mask polygon of projector `p` by the even-odd rule, or `p`
+// has none, see `MaskPolygon::contains`
+fn in_mask(p: ProjectorUniform, tex_coords: vec2<f32>) -> bool {
+    if p.mask_len == 0u {
+        return true;
+    }
+    var mask = p.mask;
+    var inside = false;
+    var j = p.mask_len - 1u;
+    for (var i = 0u; i < p.mask_len; i++) {
+        let a = select(mask[i / 2u].xy, mask[i / 2u].zw, i % 2u == 1u);
+        let b = select(mask[j / 2u].xy, mask[j / 2u].zw, j % 2u == 1u);
+        let crosses = (a.y > tex_coords.y) != (b.y > tex_coords.y);
+        if crosses && tex_coords.x < (b.x - a.x) * (tex_coords.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    return inside;
+}
+
+// Whether the image of projector `p` covers a point at `position` in its clip space
+fn is_projected(p: ProjectorUniform, position: vec4<f32>, tex_coords: vec2<f32>) -> bool {
+    let inside = all(tex_coords >= vec2<f32>(0.0)) && all(tex_coords <= vec2<f32>(1.0))
+        && in_mask(p, tex_coords);
+    // Past the projector `w` turns negative and the divide flips the image over
+    let in_front = position.w > 0.0 || p.reject_behind == 0u;
+    return inside && in_front;
+}
+
+// `color` sampled from the image of projector `p`, blended over its base color by its alpha if
+// `alpha_as_mask` is set
+fn masked(p: ProjectorUniform, color: vec4<f32>) -> vec4<f32> {
+    let blended = vec4<f32>(mix(p.base_color.rgb, color.rgb, color.a), p.base_color.a);
+    return select(color, blended, p.alpha_as_mask != 0u);
+}
+
+// The projected color, or the base color outside the image.  Sampled either way since
+// `textureSample` needs uniform control flow.
+fn or_base_color(in: VertexOutput, tex_coords: vec2<f32>, color: vec4<f32>) -> vec4<f32> {
+    let projected = is_projected(projector, in.projector_position, tex_coords);
+    return select(projector.base_color, masked(projector, color), projected);
+}
+
+fn sample_projection(in: VertexOutput) -> vec4<f32> {
+    let tex_coords = projected_tex_coords(projector, in.projector_position);
+    return or_base_color(in, tex_coords, textureSample(t_diffuse, s_diffuse, tex_coords));
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let tex_coords = clamp(in.tex_coords, vec2<f32>(0.0), vec2<f32>(1.0));
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return shade(in, sample_projection(in));
+}
+
+// G-buffer of `Renderer::render_gbuffer`: the frame like `fs_main`, then the data it's shaded
+// from, each in a target of its own
+struct GBufferOutput {
+    @location(0) color: vec4<f32>,
+    // World space normal, and 1 in `w` where a mesh is drawn
+    @location(1) normal: vec4<f32>,
+    // Projector texture coordinates, -1 where the image doesn't cover the surface
+    @location(2) tex_coords: vec2<f32>,
+    // World space distance from the camera's eye
+    @location(3) depth: f32,
+};
+
+@fragment
+fn fs_gbuffer(in: VertexOutput) -> GBufferOutput {
+    let tex_coords = projected_tex_coords(projector, in.projector_position);
+    let projected = is_projected(projector, in.projector_position, tex_coords);
+    var out: GBufferOutput;
+    out.color = shade(in, sample_projection(in));
+    out.normal = vec4<f32>(normalize(in.world_normal), 1.0);
+    out.tex_coords = select(vec2<f32>(-1.0), tex_coords, projected);
+    out.depth = distance(in.world_position, camera.view_pos.xyz);
+    return out;
+}
+
+@fragment
+fn fs_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in, sample_projection(in));
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// Image sequence packed into one array texture, see `Texture::from_image_sequence`
+
+@group(3) @binding(0)
+var t_frames: texture_2d_array<f32>;
+@group(3) @binding(1)
+var s_frames: sampler;
+// Only `x` is used, the layer to show; a `vec4` keeps the uniform 16 bytes for WebGL
+@group(3) @binding(2)
+var<uniform> frame: vec4<u32>;
+
+@fragment
+fn fs_flipbook(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_coords = projected_tex_coords(projector, in.projector_position);
+    let color = textureSample(t_frames, s_frames, tex_coords, frame.x);
+    return shade(in, or_base_color(in, tex_coords, color));
 }
 
+// Projectors added with `State::add_projector`, each image in its own layer
+
+struct ProjectorSet {
+    // Only `x` is used, the number of projectors
+    count: vec4<u32>,
+    // Length must match `MAX_PROJECTORS`
+    projectors: array<ProjectorUniform, 8>,
+};
+
+@group(3) @binding(0)
+var t_projectors: texture_2d_array<f32>;
+@group(3) @binding(1)
+var s_projectors: sampler;
+@group(3) @binding(2)
+var<uniform> projector_set: ProjectorSet;
+
+// Weighted average of the projectors covering the fragment, so overlaps stay as bright as a
+// single projector instead of adding up
+@fragment
+fn fs_blend(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = vec4<f32>(0.0);
+    var weight = 0.0;
+    for (var i = 0u; i < projector_set.count.x; i++) {
+        let p = projector_set.projectors[i];
+        let position = p.view_proj * vec4<f32>(in.world_position, 1.0);
+        let tex_coords = projected_tex_coords(p, position);
+        let sampled = textureSampleLevel(t_projectors, s_projectors, tex_coords, i, 0.0);
+        let projected = masked(p, sampled);
+        let w = select(0.0, p.weight, is_projected(p, position, tex_coords));
+        color += projected * w;
+        weight += w;
+    }
+    return shade(in, select(projector.base_color, color / weight, weight > 0.0));
+}
+
+// Debug view of how many projectors cover each fragment instead of their images, to plan
+// blends: black for none, blue for one, green for two and red for three or more
+
+fn coverage_color(count: u32) -> vec4<f32> {
+    var ramp = array<vec4<f32>, 4>(
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        vec4<f32>(0.0, 0.0, 1.0, 1.0),
+        vec4<f32>(0.0, 1.0, 0.0, 1.0),
+        vec4<f32>(1.0, 0.0, 0.0, 1.0),
+    );
+    return ramp[min(count, 3u)];
+}
 
+// Counts the projectors of `projector_set`, like `fs_blend`
+@fragment
+fn fs_coverage(in: VertexOutput) -> @location(0) vec4<f32> {
+    var count = 0u;
+    for (var i = 0u; i < projector_set.count.x; i++) {
+        let p = projector_set.projectors[i];
+        let position = p.view_proj * vec4<f32>(in.world_position, 1.0);
+        let tex_coords = projected_tex_coords(p, position);
+        count += u32(p.weight > 0.0 && is_projected(p, position, tex_coords));
+    }
+    return coverage_color(count);
+}
+
+// Only the scene's projector, without a projector set
+@fragment
+fn fs_coverage_single(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_coords = projected_tex_coords(projector, in.projector_position);
+    return coverage_color(u32(is_projected(projector, in.projector_position, tex_coords)));
+}
+
+// GPU picking, see `Renderer::pick_gpu`: the world position of the fragment and the id of its
+// mesh plus 1, which leaves 0 for nothing drawn.  Only `x` is used, the id.
+@group(3) @binding(0)
+var<uniform> pick_id: vec4<u32>;
+
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.world_position, f32(pick_id.x + 1u));
+}
+
+@fragment
+fn fs_wire(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+}
+
+// Line list meshes like the projector frustum outline, which have no surface to shade and are
+// drawn in their vertex colors instead, see `model::LineVertex`
+
+struct LineInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+struct LineOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_lines(
+    line: LineInput,
+    instance: InstanceInput,
+) -> LineOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    var out: LineOutput;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(line.position, 1.0);
+    out.color = line.color;
+    return out;
+}
+
+@fragment
+fn fs_lines(in: LineOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
+
+// Camera-facing billboards, see `State::add_billboards`, showing their image as it is rather
+// than projected onto them
+
+struct BillboardInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+
+struct BillboardOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_billboard(
+    vertex: BillboardInput,
+    instance: InstanceInput,
+) -> BillboardOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    var out: BillboardOutput;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(vertex.position, 1.0);
+    out.tex_coords = vertex.tex_coords;
+    return out;
+}
+
+@fragment
+fn fs_billboard(in: BillboardOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    // Transparent texels would still write depth and hide billboards drawn after them
+    if color.a < 0.01 {
+        discard;
+    }
+    return color;
+}
+
+// Solid background, drawn as a single triangle covering the viewport
+
+struct BackgroundOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_background(
+    @builtin(vertex_index) index: u32,
+    @location(0) color: vec4<f32>,
+) -> BackgroundOutput {
+    var out: BackgroundOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_background(in: BackgroundOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use crate::model;
+
+/// Work submitted while rendering a frame
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub triangles: u32,
+    pub meshes_drawn: u32,
+}
+
+impl RenderStats {
+    /// Record one draw of `mesh`
+    pub fn add_mesh(&mut self, mesh: &model::Mesh, instances: u32) {
+        self.draw_calls += 1;
+        self.triangles += mesh.num_elements / 3 * instances;
+        self.meshes_drawn += 1;
+    }
+}
+
+impl std::ops::AddAssign for RenderStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.draw_calls += rhs.draw_calls;
+        self.triangles += rhs.triangles;
+        self.meshes_drawn += rhs.meshes_drawn;
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} draws, {} triangles, {} meshes",
+            self.draw_calls, self.triangles, self.meshes_drawn
+        )
+    }
+}
+
+/// Frames-per-second over a one second window
+pub struct FrameCounter {
+    frames: u32,
+    start: instant::Instant,
+}
+
+impl FrameCounter {
+    pub fn new() -> Self {
+        Self {
+            frames: 0,
+            start: instant::Instant::now(),
+        }
+    }
+
+    /// Count a frame.  Returns the frame rate once per second.
+    pub fn tick(&mut self) -> Option<f32> {
+        self.frames += 1;
+        let elapsed = self.start.elapsed().as_secs_f32();
+        if elapsed < 1.0 {
+            return None;
+        }
+        let fps = self.frames as f32 / elapsed;
+        self.frames = 0;
+        self.start = instant::Instant::now();
+        Some(fps)
+    }
+}
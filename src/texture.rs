@@ -1,22 +1,268 @@
+use anyhow::Context;
 use image::GenericImageView;
 
+use crate::dds;
+
+/// What to do with images larger than the device's `max_texture_dimension_2d`, e.g. high
+/// resolution content on WebGL which is limited to 2048 pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Oversized {
+    /// Fail with an error naming the limit
+    #[default]
+    Fail,
+    /// Shrink to fit, keeping the aspect ratio, and log a warning
+    Downscale,
+}
+
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
+    pub size: wgpu::Extent3d,
+    pub format: wgpu::TextureFormat,
 }
 
 impl Texture {
+    pub fn width(&self) -> u32 {
+        self.size.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.size.height
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Descriptor matching the underlying GPU texture, e.g. for allocating a compatible copy
+    pub fn descriptor(&self) -> wgpu::TextureDescriptor<'static> {
+        wgpu::TextureDescriptor {
+            label: None,
+            size: self.size,
+            mip_level_count: self.texture.mip_level_count(),
+            sample_count: self.texture.sample_count(),
+            dimension: self.texture.dimension(),
+            format: self.format,
+            usage: self.texture.usage(),
+            view_formats: &[],
+        }
+    }
+
+    /// Copy of the texture in a new one, e.g. to project an image that's already in use from
+    /// another projector.  Needs `COPY_SRC` usage, which loaded images have.
+    pub fn duplicate(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let descriptor = self.descriptor();
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Duplicated Texture"),
+            usage: descriptor.usage | wgpu::TextureUsages::COPY_DST,
+            ..descriptor
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Duplicate Texture Encoder"),
+        });
+        for mip_level in 0..descriptor.mip_level_count {
+            encoder.copy_texture_to_texture(
+                wgpu::ImageCopyTexture {
+                    mip_level,
+                    ..self.texture.as_image_copy()
+                },
+                wgpu::ImageCopyTexture {
+                    mip_level,
+                    ..texture.as_image_copy()
+                },
+                descriptor
+                    .mip_level_size(mip_level)
+                    .unwrap_or(descriptor.size),
+            );
+        }
+        queue.submit(Some(encoder.finish()));
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Samplers can't be copied, this is the one images are loaded with
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        Self {
+            texture,
+            view,
+            sampler,
+            size: self.size,
+            format: self.format,
+        }
+    }
+
+    /// Reallocate the GPU texture at a new size and format, e.g. when a streamed source changes
+    /// resolution.  The contents are undefined until written again, and bind groups holding the
+    /// old view must be rebuilt by the caller.
+    pub fn recreate(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        self.texture = device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            format,
+            ..self.descriptor()
+        });
+        self.view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.size = size;
+        self.format = format;
+    }
+
     pub fn from_bytes(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         bytes: &[u8],
         label: &str,
+        oversized: Oversized,
     ) -> anyhow::Result<Self> {
+        if bytes.starts_with(b"DDS ") {
+            return Self::from_dds(device, queue, bytes, label);
+        }
         let img = image::load_from_memory(bytes)?;
+        let max = device.limits().max_texture_dimension_2d;
+        let (width, height) = img.dimensions();
+        let img = if oversized == Oversized::Downscale && (width > max || height > max) {
+            log::warn!("Downscaling {label} from {width}x{height} to fit the {max} pixel limit");
+            img.resize(max, max, image::imageops::FilterType::Triangle)
+        } else {
+            img
+        };
         Self::from_image(device, queue, &img, Some(label))
     }
 
+    /// Fail unless the device can hold a `size` texture
+    fn check_size(device: &wgpu::Device, size: wgpu::Extent3d, label: &str) -> anyhow::Result<()> {
+        let max = device.limits().max_texture_dimension_2d;
+        if size.width > max || size.height > max {
+            anyhow::bail!(
+                "{label} is {}x{}, larger than this device's {max} pixel texture limit",
+                size.width,
+                size.height
+            );
+        }
+        Ok(())
+    }
+
+    /// Load a DDS image including its mip chain.  Block compressed data the device can't sample
+    /// is decoded to RGBA8 first.
+    pub fn from_dds(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> anyhow::Result<Self> {
+        let dds = ddsfile::Dds::read(bytes)?;
+        let format = dds::texture_format(&dds)
+            .with_context(|| format!("Unsupported DDS format in {label}"))?;
+        let size = wgpu::Extent3d {
+            width: dds.get_width(),
+            height: dds.get_height(),
+            depth_or_array_layers: 1,
+        };
+        Self::check_size(device, size, label)?;
+        let mip_level_count = dds.get_num_mipmap_levels().max(1);
+        let levels = dds::mip_levels(format, size, mip_level_count, dds.get_data(0)?)?;
+
+        // Compressed textures also have to be a whole number of blocks in size
+        let (block_width, block_height) = format.block_dimensions();
+        let supported = device.features().contains(format.required_features())
+            && size.width.is_multiple_of(block_width)
+            && size.height.is_multiple_of(block_height);
+        let (format, levels) = if supported {
+            (format, levels.into_iter().map(Vec::from).collect())
+        } else {
+            let decoded = levels
+                .into_iter()
+                .enumerate()
+                .map(|(level, data)| {
+                    let mip = size.mip_level_size(level as u32, wgpu::TextureDimension::D2);
+                    dds::decompress(format, mip.width, mip.height, data)
+                })
+                .collect::<Option<Vec<_>>>()
+                .with_context(|| format!("{format:?} in {label} is not supported by the device"))?;
+            log::info!("Decoded {format:?} image {label} on the CPU");
+            let rgba = if format.is_srgb() {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            };
+            (rgba, decoded)
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_size(None).unwrap_or(4);
+        for (level, data) in levels.iter().enumerate() {
+            let mip = size
+                .mip_level_size(level as u32, wgpu::TextureDimension::D2)
+                .physical_size(format);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(mip.width / block_width * block_size),
+                    rows_per_image: Some(mip.height / block_height),
+                },
+                mip,
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format,
+        })
+    }
+
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -30,15 +276,20 @@ impl Texture {
             height,
             depth_or_array_layers: 1,
         };
+        Self::check_size(device, size, label.unwrap_or("Image"))?;
 
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            format,
+            // Copied into a layer of `State::add_projector`'s array texture
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -74,13 +325,189 @@ impl Texture {
             texture,
             view,
             sampler,
+            size,
+            format,
         })
     }
 
+    /// Pack equally sized `frames` into the layers of one array texture, so switching frames
+    /// needs no new bind group.  The view is a `D2Array`.
+    pub fn from_image_sequence(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        frames: &[image::DynamicImage],
+        label: &str,
+    ) -> anyhow::Result<Self> {
+        let first = frames.first().context("Image sequence has no frames")?;
+        let (width, height) = first.dimensions();
+        if let Some(i) = frames
+            .iter()
+            .position(|f| f.dimensions() != (width, height))
+        {
+            let (w, h) = frames[i].dimensions();
+            anyhow::bail!("Frame {i} of {label} is {w}x{h}, the first frame is {width}x{height}");
+        }
+        let layers = frames.len() as u32;
+        let max_layers = device.limits().max_texture_array_layers;
+        if layers > max_layers {
+            anyhow::bail!("{label} has {layers} frames, more than the {max_layers} layer limit");
+        }
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers,
+        };
+        Self::check_size(device, size, label)?;
+
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, frame) in frames.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &frame.to_rgba8(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..size
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format,
+        })
+    }
+
+    /// Color attachment for rendering off-screen that can be copied out afterwards, or sampled
+    /// like a loaded image
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format,
+        }
+    }
+
+    /// Color attachment with `sample_count` samples per pixel, resolved into the surface
+    pub fn create_multisampled_target(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+        label: &str,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+            format: config.format,
+        }
+    }
+
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    /// Depth attachment the size of `config`, with `sample_count` matching the color attachment
     pub fn create_depth_texture(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
         label: &str,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -88,14 +515,21 @@ impl Texture {
             height: config.height,
             depth_or_array_layers: 1,
         };
+        // A multisampled depth texture that can also be sampled keeps the GL backend from
+        // resolving the color attachment, and nothing samples it anyway
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
         let desc = wgpu::TextureDescriptor {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage,
             view_formats: &[],
         };
 
@@ -119,6 +553,8 @@ impl Texture {
             texture,
             view,
             sampler,
+            size,
+            format: Self::DEPTH_FORMAT,
         }
     }
 
@@ -163,6 +599,8 @@ impl Texture {
             texture,
             view,
             sampler,
+            size,
+            format: Self::DEPTH_FORMAT,
         }
     }
 }
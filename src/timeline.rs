@@ -0,0 +1,122 @@
+/// Playback position for animated content, e.g. flipping through the loaded images.  Unlike
+/// continuous rendering it can be paused, stepped and scrubbed, so any frame can be reproduced.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timeline {
+    /// Length in seconds
+    pub duration: f32,
+    /// Wrap around at the ends instead of stopping
+    pub looping: bool,
+    pub playing: bool,
+    time: f32,
+}
+
+impl Timeline {
+    /// A playing timeline starting at zero.  `duration` is clamped to be positive.
+    pub fn new(duration: f32, looping: bool) -> Self {
+        Self {
+            duration: duration.max(f32::EPSILON),
+            looping,
+            playing: true,
+            time: 0.0,
+        }
+    }
+
+    /// Current position in seconds, in `[0, duration]`
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Move to `time`, wrapping or clamping it to the timeline.  Playback stops at the end of a
+    /// timeline that doesn't loop.
+    pub fn set_time(&mut self, time: f32) {
+        if self.looping {
+            self.time = time.rem_euclid(self.duration);
+        } else {
+            self.time = time.clamp(0.0, self.duration);
+            if self.time == self.duration {
+                self.playing = false;
+            }
+        }
+    }
+
+    /// Advance by `dt` seconds if playing
+    pub fn advance(&mut self, dt: f32) {
+        if self.playing {
+            self.set_time(self.time + dt);
+        }
+    }
+
+    pub fn toggle_playing(&mut self) {
+        if !self.playing && !self.looping && self.time == self.duration {
+            self.time = 0.0;
+        }
+        self.playing = !self.playing;
+    }
+
+    /// Which of `count` equally long frames is showing
+    pub fn frame(&self, count: usize) -> usize {
+        let frame = (self.time / self.duration * count as f32) as usize;
+        frame.min(count.saturating_sub(1))
+    }
+
+    /// Pause and move `frames` frames forwards or backwards, for a timeline split into `count`
+    /// frames.  Lands in the middle of the frame so rounding can't pick its neighbour.
+    pub fn step(&mut self, frames: i32, count: usize) {
+        self.playing = false;
+        let count = count.max(1);
+        let frame = self.frame(count) as i32 + frames;
+        self.set_time((frame as f32 + 0.5) * self.duration / count as f32);
+    }
+
+    /// Move by `delta` seconds without changing whether it's playing
+    pub fn scrub(&mut self, delta: f32) {
+        let playing = self.playing;
+        self.set_time(self.time + delta);
+        self.playing = playing;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stepping back from the first frame wraps to the last one on a looping timeline and
+    /// stays put on one that doesn't loop
+    #[test]
+    fn step_back_from_first_frame() {
+        let mut looping = Timeline::new(2.0, true);
+        looping.step(-1, 4);
+        assert_eq!(
+            looping.frame(4),
+            3,
+            "Looping timeline at {}",
+            looping.time()
+        );
+        assert!(!looping.playing, "Stepping pauses");
+
+        let mut once = Timeline::new(2.0, false);
+        once.step(-1, 4);
+        assert_eq!(once.frame(4), 0, "Non-looping timeline at {}", once.time());
+        assert_eq!(
+            once.time(),
+            0.0,
+            "Non-looping timeline is clamped to the start"
+        );
+    }
+
+    /// The end of a looping timeline is its start, while one that doesn't loop stops on its
+    /// last frame
+    #[test]
+    fn set_time_at_duration() {
+        let mut looping = Timeline::new(2.0, true);
+        looping.set_time(2.0);
+        assert_eq!(looping.time(), 0.0, "Looping timeline wraps to the start");
+        assert!(looping.playing, "Looping timeline keeps playing");
+
+        let mut once = Timeline::new(2.0, false);
+        once.set_time(2.0);
+        assert_eq!(once.time(), 2.0, "Non-looping timeline stays at the end");
+        assert_eq!(once.frame(4), 3, "The end shows the last frame");
+        assert!(!once.playing, "Non-looping timeline stops at the end");
+    }
+}
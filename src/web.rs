@@ -0,0 +1,99 @@
+//! Browser glue: projecting image files picked on the page or dropped onto the canvas
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use winit::event_loop::EventLoopProxy;
+
+use crate::UserEvent;
+
+/// Read `file` and send it to the event loop, made into a [`UserEvent`] by `event`, once it's
+/// loaded
+fn load_file(
+    file: web_sys::File,
+    proxy: EventLoopProxy<UserEvent>,
+    event: fn(String, Vec<u8>) -> UserEvent,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let name = file.name();
+        match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+            Ok(buffer) => {
+                let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                // The event loop is gone once the proxy fails, nothing left to show it in
+                let _ = proxy.send_event(event(name, bytes));
+            }
+            Err(e) => log::warn!("Couldn't read {name}: {e:?}"),
+        }
+    });
+}
+
+/// Project the image picked with the page's `<input type="file" id="image_file">`, if it has one
+pub fn watch_file_input(document: &web_sys::Document, proxy: EventLoopProxy<UserEvent>) {
+    let Some(input) = document
+        .get_element_by_id("image_file")
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok())
+    else {
+        return;
+    };
+    let on_change = Closure::<dyn FnMut()>::new({
+        let input = input.clone();
+        move || {
+            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                let event = |name, bytes| UserEvent::ImageLoaded { name, bytes };
+                load_file(file, proxy.clone(), event);
+            }
+        }
+    });
+    let listening =
+        input.add_event_listener_with_callback("change", on_change.as_ref().unchecked_ref());
+    if let Err(e) = listening {
+        log::warn!("Couldn't listen for picked files: {e:?}");
+    }
+    // Listens for as long as the page is open
+    on_change.forget();
+}
+
+/// Project image files dropped onto `canvas`, each as a material of its own like files dropped
+/// onto a native window, tinting the canvas while they are dragged over it
+pub fn watch_dropped_files(canvas: &web_sys::HtmlCanvasElement, proxy: EventLoopProxy<UserEvent>) {
+    // The browser opens dropped files itself unless both of these are cancelled
+    let on_dragover = Closure::<dyn FnMut(web_sys::DragEvent)>::new({
+        let proxy = proxy.clone();
+        move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            let _ = proxy.send_event(UserEvent::FileHovered(true));
+        }
+    });
+    let on_dragleave = Closure::<dyn FnMut(web_sys::DragEvent)>::new({
+        let proxy = proxy.clone();
+        move |_: web_sys::DragEvent| {
+            let _ = proxy.send_event(UserEvent::FileHovered(false));
+        }
+    });
+    let on_drop = Closure::<dyn FnMut(web_sys::DragEvent)>::new({
+        move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            let _ = proxy.send_event(UserEvent::FileHovered(false));
+            let Some(files) = event.data_transfer().and_then(|data| data.files()) else {
+                return;
+            };
+            for file in (0..files.length()).filter_map(|i| files.get(i)) {
+                let event = |name, bytes| UserEvent::ImageDropped { name, bytes };
+                load_file(file, proxy.clone(), event);
+            }
+        }
+    });
+    for (event, listener) in [
+        ("dragover", &on_dragover),
+        ("dragleave", &on_dragleave),
+        ("drop", &on_drop),
+    ] {
+        let listening =
+            canvas.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref());
+        if let Err(e) = listening {
+            log::warn!("Couldn't listen for {event} on the canvas: {e:?}");
+        }
+    }
+    // Listen for as long as the page is open
+    on_dragover.forget();
+    on_dragleave.forget();
+    on_drop.forget();
+}